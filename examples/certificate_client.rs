@@ -33,7 +33,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
     // Connecting to APNs using a client certificate
     let new_client = || -> Result<Client, Box<dyn std::error::Error + Sync + Send>> {
-        #[cfg(feature = "openssl")]
+        #[cfg(any(feature = "cert-openssl", feature = "cert-ring"))]
         {
             // Which service to call, test or production?
             let endpoint = if sandbox {
@@ -49,9 +49,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
             Ok(Client::certificate(&mut certificate, &password, client_config)?)
         }
-        #[cfg(all(not(feature = "openssl"), feature = "ring"))]
+        #[cfg(not(any(feature = "cert-openssl", feature = "cert-ring")))]
         {
-            Err("ring does not support loading of certificates".into())
+            Err("loading certificates requires the cert-openssl or cert-ring feature".into())
         }
     };
     let client = new_client()?;
@@ -1,9 +1,6 @@
 use argparse::{ArgumentParser, Store, StoreOption, StoreTrue};
-use std::fs::File;
 
-use a2::{
-    client::ClientConfig, Client, DefaultNotificationBuilder, Endpoint, NotificationBuilder, NotificationOptions,
-};
+use a2::{Client, DefaultNotificationBuilder, NotificationBuilder, NotificationOptions};
 
 // An example client connectiong to APNs with a JWT token
 #[tokio::main]
@@ -17,6 +14,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let mut message = String::from("Ch-check it out!");
     let mut sandbox = false;
     let mut topic: Option<String> = None;
+    let mut debug_token = false;
 
     {
         let mut ap = ArgumentParser::new();
@@ -35,24 +33,42 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             .add_option(&["-s", "--sandbox"], StoreTrue, "Use the development APNs servers");
         ap.refer(&mut topic)
             .add_option(&["-o", "--topic"], StoreOption, "APNS topic");
+        ap.refer(&mut debug_token)
+            .add_option(&["--debug-token"], StoreTrue, "Print the JWT used for authentication");
         ap.parse_args_or_exit();
     }
 
-    // Read the private key from disk
-    let mut private_key = File::open(key_file).unwrap();
+    // Connecting to APNs using a signing key
+    let new_client = || -> Result<Client, Box<dyn std::error::Error + Sync + Send>> {
+        #[cfg(any(feature = "jwt-openssl", feature = "jwt-ring"))]
+        {
+            // Read the private key from disk
+            let mut private_key = std::fs::File::open(key_file)?;
 
-    // Which service to call, test or production?
-    let endpoint = if sandbox {
-        Endpoint::Sandbox
-    } else {
-        Endpoint::Production
-    };
+            // Which service to call, test or production?
+            let endpoint = if sandbox {
+                a2::Endpoint::Sandbox
+            } else {
+                a2::Endpoint::Production
+            };
+
+            // Create config with the given endpoint and default timeouts
+            let client_config = a2::ClientConfig::new(endpoint);
 
-    // Create config with the given endpoint and default timeouts
-    let client_config = ClientConfig::new(endpoint);
+            Ok(Client::token(&mut private_key, key_id, team_id, client_config)?)
+        }
+        #[cfg(not(any(feature = "jwt-openssl", feature = "jwt-ring")))]
+        {
+            Err("signing tokens requires the jwt-openssl or jwt-ring feature".into())
+        }
+    };
+    let client = new_client()?;
 
-    // Connecting to APNs
-    let client = Client::token(&mut private_key, key_id, team_id, client_config).unwrap();
+    if debug_token {
+        if let Some(description) = client.describe_token() {
+            println!("{}", description);
+        }
+    }
 
     let options = NotificationOptions {
         apns_topic: topic.as_deref(),
@@ -0,0 +1,227 @@
+//! Bounded caches for long-running senders
+
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// A snapshot of the size of a cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of entries currently stored
+    pub len: usize,
+    /// The maximum number of entries stored before evicting
+    pub capacity: usize,
+    /// The number of entries evicted to stay within the capacity
+    pub evictions: u64,
+}
+
+/// A map holding at most `capacity` entries. Inserting into a full cache
+/// evicts the least recently used entry, so memory stays bounded no matter
+/// how long the process runs.
+///
+/// ```rust
+/// # use a2::cache::LruCache;
+/// let mut cache = LruCache::new(2);
+///
+/// cache.insert("a", 1);
+/// cache.insert("b", 2);
+/// cache.get("a");
+/// cache.insert("c", 3);
+///
+/// assert!(cache.contains_key("a"));
+/// assert!(!cache.contains_key("b"));
+/// assert_eq!(1, cache.stats().evictions);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LruCache<K, V> {
+    capacity: usize,
+    tick: u64,
+    evictions: u64,
+    entries: HashMap<K, (V, u64)>,
+    order: BTreeMap<u64, K>,
+}
+
+impl<K, V> LruCache<K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    /// Creates an empty cache holding at most `capacity` entries. A zero
+    /// capacity stores nothing.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            evictions: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    /// Inserts a value, marking it as the most recently used. Returns the
+    /// previous value for the key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if self.capacity == 0 {
+            return None;
+        }
+
+        let tick = self.next_tick();
+
+        if let Some((previous, used)) = self.entries.insert(key.clone(), (value, tick)) {
+            self.order.remove(&used);
+            self.order.insert(tick, key);
+            return Some(previous);
+        }
+
+        self.order.insert(tick, key);
+
+        while self.entries.len() > self.capacity {
+            let Some(&used) = self.order.keys().next() else {
+                break;
+            };
+
+            if let Some(oldest) = self.order.remove(&used) {
+                self.entries.remove(&oldest);
+            }
+            self.evictions += 1;
+        }
+
+        None
+    }
+
+    /// Gets a value, marking it as the most recently used.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let tick = self.next_tick();
+        let (value, used) = self.entries.get_mut(key)?;
+
+        let key = self.order.remove(used)?;
+        self.order.insert(tick, key);
+        *used = tick;
+
+        Some(value)
+    }
+
+    /// True if the key is in the cache. Does not affect the eviction order.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.contains_key(key)
+    }
+
+    /// Removes a value from the cache.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (value, used) = self.entries.remove(key)?;
+        self.order.remove(&used);
+
+        Some(value)
+    }
+
+    /// The number of entries in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if the cache holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The maximum number of entries in the cache.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The current size of the cache.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            len: self.len(),
+            capacity: self.capacity,
+            evictions: self.evictions,
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_oldest_beyond_capacity() {
+        let mut cache = LruCache::new(3);
+
+        for i in 0..5 {
+            cache.insert(i, i * 10);
+        }
+
+        assert!(!cache.contains_key(&0));
+        assert!(!cache.contains_key(&1));
+        assert!(cache.contains_key(&2));
+        assert!(cache.contains_key(&3));
+        assert!(cache.contains_key(&4));
+
+        assert_eq!(
+            CacheStats {
+                len: 3,
+                capacity: 3,
+                evictions: 2,
+            },
+            cache.stats()
+        );
+    }
+
+    #[test]
+    fn test_get_refreshes_entry() {
+        let mut cache = LruCache::new(2);
+
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+
+        assert_eq!(Some(&1), cache.get("a"));
+
+        cache.insert("c", 3);
+
+        assert!(cache.contains_key("a"));
+        assert!(!cache.contains_key("b"));
+        assert!(cache.contains_key("c"));
+    }
+
+    #[test]
+    fn test_reinsert_replaces_without_growing() {
+        let mut cache = LruCache::new(2);
+
+        assert_eq!(None, cache.insert("a", 1));
+        assert_eq!(Some(1), cache.insert("a", 2));
+
+        assert_eq!(1, cache.len());
+        assert_eq!(Some(&2), cache.get("a"));
+        assert_eq!(0, cache.stats().evictions);
+    }
+
+    #[test]
+    fn test_remove_and_zero_capacity() {
+        let mut cache = LruCache::new(2);
+
+        cache.insert("a", 1);
+        assert_eq!(Some(1), cache.remove("a"));
+        assert!(cache.is_empty());
+
+        let mut cache = LruCache::new(0);
+        cache.insert("a", 1);
+        assert!(cache.is_empty());
+        assert_eq!(0, cache.capacity());
+    }
+}
@@ -1,8 +1,16 @@
 /// Error and result module
-use crate::{response::Response, signer::SignerError};
+use crate::{
+    response::{ErrorBody, ErrorReason, Response},
+    signer::SignerError,
+};
+use std::convert::Infallible;
 use std::io;
+use std::time::Duration;
 use thiserror::Error;
 
+const ENVIRONMENT_MISMATCH_HINT: &str =
+    " Hint: the credentials or device token might be for the other APNs environment, try switching between `Endpoint::Production` and `Endpoint::Sandbox`.";
+
 #[derive(Debug, Error)]
 pub enum Error {
     /// User request or Apple response JSON data was faulty.
@@ -20,17 +28,37 @@ pub enum Error {
     #[error("Error creating a signature: {0}")]
     SignerError(#[from] SignerError),
 
-    /// APNs couldn't accept the notification. Contains
+    /// APNs couldn't accept the notification. Contains the boxed
     /// [Response](response/struct.Response.html) with additional
-    /// information.
+    /// information. Boxed to keep `Result<_, Error>` small, as the response
+    /// carries the latency and ids of the request.
     #[error(
-        "Notification was not accepted by APNs (reason: {})",
+        "Notification was not accepted by APNs (reason: {}){}",
         .0.error
             .as_ref()
             .map(|e| e.reason.to_string())
-            .unwrap_or_else(|| "Unknown".to_string())
+            .unwrap_or_else(|| "Unknown".to_string()),
+        .0.error
+            .as_ref()
+            .filter(|e| e.reason.is_environment_mismatch())
+            .map(|_| ENVIRONMENT_MISMATCH_HINT)
+            .unwrap_or_default()
     )]
-    ResponseError(Response),
+    ResponseError(Box<Response>),
+
+    /// APNs, or a proxy in between, rejected the notification with a body
+    /// that is not an APNs error, e.g. an empty body or an HTML page.
+    #[error("Notification was not accepted (HTTP {status}) with an unparseable body: {body:?}")]
+    Unparseable {
+        /// The HTTP status of the response.
+        status: u16,
+        /// The raw response body, lossily decoded as UTF-8.
+        body: String,
+        /// The `apns-id` header of the response, if any.
+        apns_id: Option<String>,
+        /// How long to wait before retrying, from the `Retry-After` header.
+        retry_after: Option<Duration>,
+    },
 
     /// Invalid option values given in
     /// [NotificationOptions](request/notification/struct.NotificationOptions.html)
@@ -52,18 +80,188 @@ pub enum Error {
     #[error("The request timed out after {0} s")]
     RequestTimeout(u64),
 
-    /// Unexpected private key (only EC keys are supported).
-    #[cfg(all(not(feature = "openssl"), feature = "ring"))]
-    #[error("Unexpected private key: {0}")]
-    UnexpectedKey(#[from] ring::error::KeyRejected),
+    /// Couldn't establish a connection to APNs in the given amount of time,
+    /// see [ClientConfig](client/struct.ClientConfig.html)'s
+    /// `connect_timeout_secs`.
+    #[error("Connecting to APNs timed out after {0} s")]
+    ConnectTimeout(u64),
 
     #[error("Invalid certificate")]
     InvalidCertificate,
+
+    /// The private key for token authentication is not an EC key on the
+    /// curve of the signing algorithm, e.g. an RSA key. With the `jwt-ring`
+    /// backend, any key `ring` rejects is reported this way.
+    #[error("Unsupported private key type: APNs requires an EC key, P-256 for ES256 as issued by Apple")]
+    UnsupportedKeyType,
+
+    /// No client was registered for the payload's `apns_topic` in a
+    /// [MultiAuthClient](client/struct.MultiAuthClient.html).
+    #[error("No client configured for topic {0:?}")]
+    UnmappedTopic(Option<String>),
+
+    /// APNs accepted the request, but the response lacked the expected
+    /// header or body.
+    #[error("Unexpected response from APNs: {0}")]
+    UnexpectedResponse(String),
+
+    /// The operation needs a client using token authentication with a
+    /// [Signer](struct.Signer.html).
+    #[error("The client does not sign its requests with a Signer")]
+    MissingSigner,
+
+    /// [Client::send_blocking](client/struct.Client.html#method.send_blocking)
+    /// couldn't drive the request, e.g. because it was called from within an
+    /// async runtime.
+    #[cfg(feature = "blocking")]
+    #[error("Can't send blocking: {0}")]
+    Blocking(String),
 }
 
-#[cfg(feature = "openssl")]
+impl Error {
+    /// True if sending the same notification again later might succeed: APNs
+    /// responded with HTTP 429, 500 or 503, or the request or connecting
+    /// timed out.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::ResponseError(response) => matches!(response.code, 429 | 500 | 503),
+            Error::Unparseable { status, .. } => matches!(status, 429 | 500 | 503),
+            Error::RequestTimeout(_) | Error::ConnectTimeout(_) => true,
+            _ => false,
+        }
+    }
+
+    /// How long APNs asked to wait before retrying, from the `Retry-After`
+    /// header of a rejection.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::ResponseError(response) => response.retry_after,
+            Error::Unparseable { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// True if APNs rejected the device token and it should be removed, see
+    /// [`Response::should_drop_token`].
+    pub fn should_drop_token(&self) -> bool {
+        match self {
+            Error::ResponseError(response) => response.should_drop_token(),
+            _ => false,
+        }
+    }
+
+    /// True if APNs rejected the provider token, meaning the token should be
+    /// regenerated or the key, key id and team id checked.
+    pub fn is_token_error(&self) -> bool {
+        match self {
+            Error::ResponseError(response) => matches!(
+                response.error,
+                Some(ErrorBody {
+                    reason: ErrorReason::ExpiredProviderToken
+                        | ErrorReason::InvalidProviderToken
+                        | ErrorReason::MissingProviderToken,
+                    ..
+                })
+            ),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(any(feature = "jwt-openssl", feature = "cert-openssl"))]
 impl From<openssl::error::ErrorStack> for Error {
     fn from(e: openssl::error::ErrorStack) -> Self {
         Self::SignerError(SignerError::OpenSSL(e))
     }
 }
+
+impl From<Infallible> for Error {
+    fn from(e: Infallible) -> Self {
+        match e {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response_error_with_code(reason: ErrorReason, code: u16) -> Error {
+        Error::ResponseError(Box::new(Response {
+            error: Some(ErrorBody {
+                reason,
+                timestamp: None,
+            }),
+            apns_id: None,
+            requested_apns_id: None,
+            apns_unique_id: None,
+            retry_after: None,
+            latency: None,
+            code,
+        }))
+    }
+
+    fn response_error(reason: ErrorReason) -> Error {
+        response_error_with_code(reason, 400)
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(response_error_with_code(ErrorReason::TooManyRequests, 429).is_retryable());
+        assert!(response_error_with_code(ErrorReason::InternalServerError, 500).is_retryable());
+        assert!(response_error_with_code(ErrorReason::ServiceUnavailable, 503).is_retryable());
+        assert!(Error::RequestTimeout(20).is_retryable());
+        assert!(Error::ConnectTimeout(10).is_retryable());
+        assert!(Error::Unparseable {
+            status: 503,
+            body: String::new(),
+            apns_id: None,
+            retry_after: None,
+        }
+        .is_retryable());
+
+        assert!(!response_error(ErrorReason::BadDeviceToken).is_retryable());
+        assert!(!response_error_with_code(ErrorReason::Unregistered, 410).is_retryable());
+        assert!(!Error::InvalidCertificate.is_retryable());
+    }
+
+    #[test]
+    fn test_is_token_error() {
+        assert!(response_error_with_code(ErrorReason::ExpiredProviderToken, 403).is_token_error());
+        assert!(response_error_with_code(ErrorReason::InvalidProviderToken, 403).is_token_error());
+        assert!(response_error_with_code(ErrorReason::MissingProviderToken, 403).is_token_error());
+
+        assert!(!response_error_with_code(ErrorReason::BadCertificate, 403).is_token_error());
+        assert!(!response_error(ErrorReason::BadDeviceToken).is_token_error());
+        assert!(!Error::RequestTimeout(20).is_token_error());
+    }
+
+    #[test]
+    fn test_should_drop_token() {
+        assert!(response_error_with_code(ErrorReason::Unregistered, 410).should_drop_token());
+        assert!(response_error(ErrorReason::BadDeviceToken).should_drop_token());
+        assert!(response_error(ErrorReason::DeviceTokenNotForTopic).should_drop_token());
+
+        assert!(!response_error(ErrorReason::BadTopic).should_drop_token());
+        assert!(!response_error_with_code(ErrorReason::TooManyRequests, 429).should_drop_token());
+        assert!(!response_error_with_code(ErrorReason::InvalidProviderToken, 403).should_drop_token());
+        assert!(!Error::RequestTimeout(20).should_drop_token());
+    }
+
+    #[test]
+    fn test_environment_mismatch_hint() {
+        let message = response_error(ErrorReason::BadCertificateEnvironment).to_string();
+        assert!(message.contains("Endpoint::Sandbox"));
+
+        let message = response_error(ErrorReason::BadEnvironmentKeyInToken).to_string();
+        assert!(message.contains("Endpoint::Sandbox"));
+
+        let message = response_error(ErrorReason::BadDeviceToken).to_string();
+        assert!(message.contains("Endpoint::Sandbox"));
+    }
+
+    #[test]
+    fn test_no_hint_for_other_errors() {
+        let message = response_error(ErrorReason::PayloadTooLarge).to_string();
+        assert!(!message.contains("Hint"));
+    }
+}
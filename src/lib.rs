@@ -15,6 +15,7 @@
 //!
 //! Building the notification payload should be done with the [DefaultNotificationBuilder](request/notification/struct.DefaultNotificationBuilder.html) for most use-cases.
 //! There is also the [WebNotificationBuilder](request/notification/struct.WebNotificationBuilder.html) in the case you need to send notifications to safari
+//! and the [LiveActivityBuilder](request/notification/struct.LiveActivityBuilder.html) for starting, updating and ending Live Activities.
 //!
 //! The payload generated by the builder [can hold a custom data
 //! section](request/payload/struct.Payload.html#method.add_custom_data),
@@ -26,7 +27,9 @@
 //!
 //! The [asynchronous client](client/struct.Client.html), works either with
 //! [certificate](client/struct.Client.html#method.certificate) or
-//! [token](client/struct.Client.html#method.token) authentication.
+//! [token](client/struct.Client.html#method.token) authentication. For more
+//! control, e.g. a custom connector, use the
+//! [ClientBuilder](client/struct.ClientBuilder.html).
 //!
 //! ## Example sending a plain notification using token authentication:
 //!
@@ -35,6 +38,8 @@
 //! # use std::fs::File;
 //! # #[tokio::main]
 //! # async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//! # #[cfg(any(feature = "jwt-openssl", feature = "jwt-ring"))]
+//! # {
 //! let mut builder = DefaultNotificationBuilder::new()
 //!     .set_body("Hi there")
 //!     .set_badge(420)
@@ -52,6 +57,7 @@
 //!
 //! let response = client.send(payload).await?;
 //! println!("Sent: {:?}", response);
+//! # }
 //! # Ok(())
 //! # }
 //! ```
@@ -60,7 +66,7 @@
 //!
 //! ```no_run
 //! #[macro_use] extern crate serde;
-//! # #[cfg(all(feature = "openssl", not(feature = "ring")))]
+//! # #[cfg(any(feature = "cert-openssl", feature = "cert-ring"))]
 //! # {
 //!
 //! use a2::{
@@ -108,9 +114,6 @@
 //! ```
 #![warn(clippy::unwrap_used)]
 
-#[cfg(not(any(feature = "openssl", feature = "ring")))]
-compile_error!("either feature \"openssl\" or feature \"ring\" has to be enabled");
-
 #[macro_use]
 extern crate serde;
 
@@ -118,19 +121,30 @@ extern crate serde;
 #[macro_use]
 extern crate serde_json;
 
+pub mod cache;
 pub mod client;
 pub mod error;
 pub mod request;
 pub mod response;
 mod signer;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 
 pub use crate::request::notification::{
-    CollapseId, DefaultNotificationBuilder, NotificationBuilder, NotificationOptions, Priority, PushType,
+    BadgeNotificationBuilder, CollapseId, DefaultNotificationBuilder, InterruptionLevel, LiveActivityBuilder,
+    LiveActivityEvent, NotificationBuilder, NotificationOptions, Priority, PushType, SimpleAlertBuilder, TopicSuffix,
     WebNotificationBuilder, WebPushAlert,
 };
 
 pub use crate::response::{ErrorBody, ErrorReason, Response};
 
-pub use crate::client::{Client, ClientConfig, Endpoint};
+pub use crate::client::{
+    AuthMode, Client, ClientBuilder, ClientConfig, ClientStats, DedupConfig, Endpoint, MessageStoragePolicy,
+    MultiAuthClient, ProxyConfig, RetryPolicy, SendObserver, SendOutcome, SharedConnector,
+};
+
+pub use crate::signer::Authenticator;
+#[cfg(any(feature = "jwt-openssl", feature = "jwt-ring"))]
+pub use crate::signer::{Algorithm, Signer};
 
 pub use crate::error::Error;
@@ -1,15 +1,23 @@
 /// The `aps` notification content builders
+mod badge;
 mod default;
+mod live_activity;
 mod options;
+mod simple;
 mod web;
 
-pub use self::default::{DefaultAlert, DefaultNotificationBuilder, DefaultSound};
-pub use self::options::{CollapseId, NotificationOptions, Priority, PushType};
+pub use self::badge::BadgeNotificationBuilder;
+pub use self::default::{DefaultAlert, DefaultNotificationBuilder, DefaultSound, InterruptionLevel};
+pub use self::live_activity::{LiveActivityBuilder, LiveActivityEvent};
+pub use self::options::{CollapseId, NotificationOptions, Priority, PushType, TopicSuffix};
+pub use self::simple::SimpleAlertBuilder;
 pub use self::web::{WebNotificationBuilder, WebPushAlert};
 
 use crate::request::payload::Payload;
 
 pub trait NotificationBuilder<'a> {
-    /// Generates the request payload to be send with the `Client`.
+    /// Generates the request payload to be send with the `Client`. Sets the
+    /// `apns_push_type` fitting the payload, if the options leave it unset,
+    /// as Apple recommends sending it with every notification.
     fn build(self, device_token: &'a str, options: NotificationOptions<'a>) -> Payload<'a>;
 }
@@ -0,0 +1,75 @@
+use crate::request::notification::{NotificationBuilder, NotificationOptions, PushType};
+use crate::request::payload::{Payload, APS};
+use std::collections::BTreeMap;
+
+/// A builder to create a payload that only updates the badge of the app
+/// icon, without any visible or audible alert.
+///
+/// Badge updates are still delivered with the `alert` push type, which the
+/// builder sets unless the options choose another `apns_push_type`.
+///
+/// # Example
+///
+/// ```rust
+/// # use a2::request::notification::{BadgeNotificationBuilder, NotificationBuilder};
+/// # use a2::request::payload::PayloadLike;
+/// # fn main() {
+/// let payload = BadgeNotificationBuilder::new(3).build("token", Default::default());
+///
+/// assert_eq!(
+///     "{\"aps\":{\"badge\":3}}",
+///     &payload.to_json_string().unwrap()
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BadgeNotificationBuilder {
+    badge: u32,
+}
+
+impl BadgeNotificationBuilder {
+    /// Creates a new builder setting the badge to `badge`. Zero removes the
+    /// badge.
+    pub fn new(badge: u32) -> BadgeNotificationBuilder {
+        BadgeNotificationBuilder { badge }
+    }
+}
+
+impl<'a> NotificationBuilder<'a> for BadgeNotificationBuilder {
+    fn build(self, device_token: &'a str, mut options: NotificationOptions<'a>) -> Payload<'a> {
+        options.apns_push_type.get_or_insert(PushType::Alert);
+
+        Payload {
+            aps: APS {
+                badge: Some(self.badge),
+                ..Default::default()
+            },
+            device_token,
+            options,
+            data: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::payload::PayloadLike;
+
+    #[test]
+    fn test_badge_only_notification() {
+        let payload = BadgeNotificationBuilder::new(42)
+            .build("device-token", Default::default())
+            .to_json_string()
+            .unwrap();
+
+        assert_eq!(r#"{"aps":{"badge":42}}"#, payload);
+    }
+
+    #[test]
+    fn test_badge_only_push_type() {
+        let payload = BadgeNotificationBuilder::new(42).build("device-token", Default::default());
+
+        assert_eq!(Some(PushType::Alert), payload.options.apns_push_type);
+    }
+}
@@ -1,5 +1,7 @@
-use crate::request::notification::{NotificationBuilder, NotificationOptions};
-use crate::request::payload::{APSAlert, APSSound, Payload, APS};
+use crate::error::Error;
+use crate::request::notification::{NotificationBuilder, NotificationOptions, PushType};
+use crate::request::payload::{check_root_key, APSAlert, APSSound, Payload, APS};
+use serde_json::Value;
 
 use std::{borrow::Cow, collections::BTreeMap};
 
@@ -39,11 +41,11 @@ mod bool_as_u8 {
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct DefaultSound<'a> {
-    #[serde(skip_serializing_if = "std::ops::Not::not", with = "bool_as_u8")]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not", with = "bool_as_u8")]
     critical: bool,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    name: Option<&'a str>,
+    name: Option<Cow<'a, str>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     volume: Option<f64>,
@@ -53,31 +55,64 @@ pub struct DefaultSound<'a> {
 #[serde(rename_all = "kebab-case")]
 pub struct DefaultAlert<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
-    title: Option<&'a str>,
+    title: Option<Cow<'a, str>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    subtitle: Option<&'a str>,
+    subtitle: Option<Cow<'a, str>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    body: Option<&'a str>,
+    body: Option<Cow<'a, str>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    title_loc_key: Option<&'a str>,
+    title_loc_key: Option<Cow<'a, str>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     title_loc_args: Option<Vec<Cow<'a, str>>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    action_loc_key: Option<&'a str>,
+    action_loc_key: Option<Cow<'a, str>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    loc_key: Option<&'a str>,
+    loc_key: Option<Cow<'a, str>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     loc_args: Option<Vec<Cow<'a, str>>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    launch_image: Option<&'a str>,
+    launch_image: Option<Cow<'a, str>>,
+}
+
+impl<'a> DefaultAlert<'a> {
+    /// An alert with only a title and a body.
+    pub(crate) fn title_and_body(title: &'a str, body: &'a str) -> Self {
+        DefaultAlert {
+            title: Some(title.into()),
+            subtitle: None,
+            body: Some(body.into()),
+            title_loc_key: None,
+            title_loc_args: None,
+            action_loc_key: None,
+            loc_key: None,
+            loc_args: None,
+            launch_image: None,
+        }
+    }
+}
+
+/// The importance and delivery timing of a notification.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum InterruptionLevel {
+    /// Added to the notification list without lighting up the screen or
+    /// playing a sound.
+    Passive,
+    /// Presented immediately, lights up the screen and can play a sound.
+    Active,
+    /// Presented immediately, breaking through system notification controls.
+    TimeSensitive,
+    /// Presented immediately, bypassing the mute switch. Requires the
+    /// critical alerts entitlement.
+    Critical,
 }
 
 /// A builder to create an APNs payload.
@@ -113,10 +148,18 @@ pub struct DefaultNotificationBuilder<'a> {
     alert: DefaultAlert<'a>,
     badge: Option<u32>,
     sound: DefaultSound<'a>,
+    sound_dictionary: bool,
+    thread_id: Option<&'a str>,
     category: Option<&'a str>,
     mutable_content: u8,
     content_available: Option<u8>,
+    url_args: Option<&'a [&'a str]>,
+    target_content_id: Option<&'a str>,
+    interruption_level: Option<InterruptionLevel>,
+    relevance_score: Option<f64>,
     has_edited_alert: bool,
+    custom_data: BTreeMap<Cow<'a, str>, Value>,
+    custom_data_error: Option<String>,
 }
 
 impl<'a> DefaultNotificationBuilder<'a> {
@@ -132,7 +175,7 @@ impl<'a> DefaultNotificationBuilder<'a> {
     ///     .build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"body\":\"a body\"},\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"body\":\"a body\"}}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
@@ -156,13 +199,47 @@ impl<'a> DefaultNotificationBuilder<'a> {
                 name: None,
                 volume: None,
             },
+            sound_dictionary: false,
+            thread_id: None,
             category: None,
             mutable_content: 0,
             content_available: None,
+            url_args: None,
+            target_content_id: None,
+            interruption_level: None,
+            relevance_score: None,
             has_edited_alert: false,
+            custom_data: BTreeMap::new(),
+            custom_data_error: None,
         }
     }
 
+    /// A builder with most of the fields of a visible notification set to
+    /// placeholder values, e.g. to generate example payloads for API
+    /// documentation.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// let payload = DefaultNotificationBuilder::example().build("token", Default::default());
+    ///
+    /// println!("{}", payload.to_json_pretty().unwrap());
+    /// ```
+    pub fn example() -> DefaultNotificationBuilder<'static> {
+        DefaultNotificationBuilder::new()
+            .set_title("Order shipped")
+            .set_subtitle("Order #1234")
+            .set_body("Your order is on its way.")
+            .set_badge(1)
+            .set_sound("default")
+            .set_thread_id("orders")
+            .set_category("ORDER_UPDATE")
+            .set_mutable_content()
+            .set_target_content_id("order-1234")
+            .set_interruption_level(InterruptionLevel::TimeSensitive)
+            .set_relevance_score(0.5)
+    }
+
     /// Set the title of the notification.
     /// Apple Watch displays this string in the short look notification interface.
     /// Specify a string that’s quickly understood by the user.
@@ -176,19 +253,22 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"}}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
     /// ```
     pub fn set_title(mut self, title: &'a str) -> Self {
-        self.alert.title = Some(title);
+        self.alert.title = Some(title.into());
         self.has_edited_alert = true;
         self
     }
 
     /// Set critical alert value for this notification
-    /// Volume can only be set when the notification is marked as critcial
+    /// Volume can only be set when the notification is marked as critcial,
+    /// Apple applies it only to critical alerts. A volume given with
+    /// `critical` set to `false` is dropped, use
+    /// [`try_set_critical`](#method.try_set_critical) to reject it instead.
     /// Note: You'll need the [critical alerts entitlement](https://developer.apple.com/contact/request/notifications-critical-alerts-entitlement/) to use `true`!
     ///
     /// ```rust
@@ -200,7 +280,7 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"sound\":{\"critical\":1},\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"sound\":{\"critical\":1}}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
@@ -216,6 +296,25 @@ impl<'a> DefaultNotificationBuilder<'a> {
         self
     }
 
+    /// Like [`set_critical`](#method.set_critical), but fails with
+    /// [`Error::InvalidOptions`] if a volume is given for a notification
+    /// that is not critical.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::DefaultNotificationBuilder;
+    /// assert!(DefaultNotificationBuilder::new().try_set_critical(true, Some(0.5)).is_ok());
+    /// assert!(DefaultNotificationBuilder::new().try_set_critical(false, Some(0.5)).is_err());
+    /// ```
+    pub fn try_set_critical(self, critical: bool, volume: Option<f64>) -> Result<Self, Error> {
+        if !critical && volume.is_some() {
+            return Err(Error::InvalidOptions(String::from(
+                "The volume can only be set for critical notifications.",
+            )));
+        }
+
+        Ok(self.set_critical(critical, volume))
+    }
+
     /// Used to set the subtitle which should provide additional information that explains the purpose of the notification.
     ///
     /// ```rust
@@ -227,13 +326,13 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":{\"subtitle\":\"a subtitle\"},\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":{\"subtitle\":\"a subtitle\"}}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
     /// ```
     pub fn set_subtitle(mut self, subtitle: &'a str) -> Self {
-        self.alert.subtitle = Some(subtitle);
+        self.alert.subtitle = Some(subtitle.into());
         self.has_edited_alert = true;
         self
     }
@@ -249,17 +348,18 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":\"a body\",\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":\"a body\"}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
     /// ```
     pub fn set_body(mut self, body: &'a str) -> Self {
-        self.alert.body = Some(body);
+        self.alert.body = Some(body.into());
         self
     }
 
-    /// A number to show on a badge on top of the app icon.
+    /// A number to show on a badge on top of the app icon. Zero removes the
+    /// badge, see [`clear_badge`](#method.clear_badge).
     ///
     /// ```rust
     /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
@@ -270,7 +370,7 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"badge\":4,\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"badge\":4}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
@@ -280,6 +380,52 @@ impl<'a> DefaultNotificationBuilder<'a> {
         self
     }
 
+    /// Removes the badge from the app icon by sending a badge of zero.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let mut builder = DefaultNotificationBuilder::new()
+    ///     .clear_badge();
+    /// let payload = builder.build("token", Default::default());
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"badge\":0}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn clear_badge(mut self) -> Self {
+        self.badge = Some(0);
+        self
+    }
+
+    /// Omits the badge from the payload, undoing an earlier
+    /// [`set_badge`](#method.set_badge) or
+    /// [`clear_badge`](#method.clear_badge). Without the key the device
+    /// leaves the current badge as it is.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let mut builder = DefaultNotificationBuilder::new()
+    ///     .set_badge(4)
+    ///     .leave_badge_unchanged();
+    /// let payload = builder.build("token", Default::default());
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn leave_badge_unchanged(mut self) -> Self {
+        self.badge = None;
+        self
+    }
+
     /// File name of the custom sound to play when receiving the notification.
     ///
     /// ```rust
@@ -292,13 +438,63 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"sound\":\"ping\",\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"sound\":\"ping\"}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
     /// ```
     pub fn set_sound(mut self, sound: &'a str) -> Self {
-        self.sound.name = Some(sound);
+        self.sound.name = Some(sound.into());
+        self
+    }
+
+    /// Sends the sound as a dictionary with a name and a volume between 0
+    /// and 1, without marking the notification critical. APNs accepts the
+    /// dictionary for any notification, but the device applies the volume
+    /// only to critical alerts, see [`set_critical`](#method.set_critical).
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let mut builder = DefaultNotificationBuilder::new()
+    ///     .set_title("a title")
+    ///     .set_sound_dictionary("ping", Some(0.5));
+    /// let payload = builder.build("token", Default::default());
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"sound\":{\"name\":\"ping\",\"volume\":0.5}}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn set_sound_dictionary(mut self, sound: &'a str, volume: Option<f64>) -> Self {
+        self.sound.name = Some(sound.into());
+        self.sound.volume = volume;
+        self.sound_dictionary = true;
+        self
+    }
+
+    /// An identifier to group related notifications together in the
+    /// notification center.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let mut builder = DefaultNotificationBuilder::new()
+    ///     .set_title("a title")
+    ///     .set_thread_id("chat-42");
+    /// let payload = builder.build("token", Default::default());
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"thread-id\":\"chat-42\"}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn set_thread_id(mut self, thread_id: &'a str) -> Self {
+        self.thread_id = Some(thread_id);
         self
     }
 
@@ -315,7 +511,7 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"category\":\"cat1\",\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"category\":\"cat1\"}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
@@ -337,18 +533,19 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"title-loc-key\":\"play\"},\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"title-loc-key\":\"play\"}}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
     /// ```
     pub fn set_title_loc_key(mut self, key: &'a str) -> Self {
-        self.alert.title_loc_key = Some(key);
+        self.alert.title_loc_key = Some(key.into());
         self.has_edited_alert = true;
         self
     }
 
-    /// Arguments for the title localization.
+    /// Arguments for the title localization. An empty slice omits the key
+    /// and clears previously set arguments.
     ///
     /// ```rust
     /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
@@ -360,7 +557,7 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"title-loc-args\":[\"foo\",\"bar\"]},\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"title-loc-args\":[\"foo\",\"bar\"]}}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
@@ -369,6 +566,11 @@ impl<'a> DefaultNotificationBuilder<'a> {
     where
         S: Into<Cow<'a, str>> + AsRef<str>,
     {
+        if args.is_empty() {
+            self.alert.title_loc_args = None;
+            return self;
+        }
+
         let converted = args.iter().map(|a| a.as_ref().into()).collect();
 
         self.alert.title_loc_args = Some(converted);
@@ -388,13 +590,13 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"action-loc-key\":\"stop\"},\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"action-loc-key\":\"stop\"}}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
     /// ```
     pub fn set_action_loc_key(mut self, key: &'a str) -> Self {
-        self.alert.action_loc_key = Some(key);
+        self.alert.action_loc_key = Some(key.into());
         self.has_edited_alert = true;
         self
     }
@@ -411,18 +613,19 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"loc-key\":\"lol\"},\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"loc-key\":\"lol\"}}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
     /// ```
     pub fn set_loc_key(mut self, key: &'a str) -> Self {
-        self.alert.loc_key = Some(key);
+        self.alert.loc_key = Some(key.into());
         self.has_edited_alert = true;
         self
     }
 
-    /// Arguments for the content localization.
+    /// Arguments for the content localization. An empty slice omits the key
+    /// and clears previously set arguments.
     ///
     /// ```rust
     /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
@@ -434,7 +637,7 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"loc-args\":[\"omg\",\"foo\"]},\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"loc-args\":[\"omg\",\"foo\"]}}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
@@ -443,6 +646,11 @@ impl<'a> DefaultNotificationBuilder<'a> {
     where
         S: Into<Cow<'a, str>> + AsRef<str>,
     {
+        if args.is_empty() {
+            self.alert.loc_args = None;
+            return self;
+        }
+
         let converted = args.iter().map(|a| a.as_ref().into()).collect();
 
         self.alert.loc_args = Some(converted);
@@ -462,18 +670,19 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"launch-image\":\"cat.png\"},\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\",\"launch-image\":\"cat.png\"}}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
     /// ```
     pub fn set_launch_image(mut self, image: &'a str) -> Self {
-        self.alert.launch_image = Some(image);
+        self.alert.launch_image = Some(image.into());
         self.has_edited_alert = true;
         self
     }
 
-    /// Allow client to modify push content before displaying.
+    /// Allow client to modify push content before displaying. The
+    /// `mutable-content` key is left out of the payload otherwise.
     ///
     /// ```rust
     /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
@@ -507,7 +716,7 @@ impl<'a> DefaultNotificationBuilder<'a> {
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"content-available\":1,\"mutable-content\":0}}",
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"content-available\":1}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
@@ -516,30 +725,224 @@ impl<'a> DefaultNotificationBuilder<'a> {
         self.content_available = Some(1);
         self
     }
+
+    /// The values filled into the placeholders of the `urlFormatString` of a
+    /// Safari website push, like
+    /// [`WebNotificationBuilder`](super::WebNotificationBuilder) sends them.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let mut builder = DefaultNotificationBuilder::new()
+    ///     .set_title("a title")
+    ///     .set_url_args(&["boarding", "A998"]);
+    /// let payload = builder.build("token", Default::default());
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"url-args\":[\"boarding\",\"A998\"]}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn set_url_args(mut self, url_args: &'a [&'a str]) -> Self {
+        self.url_args = Some(url_args);
+        self
+    }
+
+    /// The identifier of the app window to bring forward when the user opens
+    /// the notification.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let mut builder = DefaultNotificationBuilder::new()
+    ///     .set_title("a title")
+    ///     .set_target_content_id("chat-42");
+    /// let payload = builder.build("token", Default::default());
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"target-content-id\":\"chat-42\"}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn set_target_content_id(mut self, id: &'a str) -> Self {
+        self.target_content_id = Some(id);
+        self
+    }
+
+    /// Routes the notification to a specific scene of a multi-window app.
+    ///
+    /// APNs has no separate scene key: the scene is selected through
+    /// `target-content-id`, which the app matches against the
+    /// `targetContentIdentifier` of its scenes. This is the same as calling
+    /// [`set_target_content_id`](Self::set_target_content_id).
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let mut builder = DefaultNotificationBuilder::new()
+    ///     .set_title("a title")
+    ///     .set_target_scene("document-7");
+    /// let payload = builder.build("token", Default::default());
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"target-content-id\":\"document-7\"}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn set_target_scene(self, scene_id: &'a str) -> Self {
+        self.set_target_content_id(scene_id)
+    }
+
+    /// The importance and delivery timing of the notification.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, InterruptionLevel, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let mut builder = DefaultNotificationBuilder::new()
+    ///     .set_title("a title")
+    ///     .set_interruption_level(InterruptionLevel::TimeSensitive);
+    /// let payload = builder.build("token", Default::default());
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"interruption-level\":\"time-sensitive\"}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn set_interruption_level(mut self, level: InterruptionLevel) -> Self {
+        self.interruption_level = Some(level);
+        self
+    }
+
+    /// A score between 0 and 1 the system uses to sort the notifications
+    /// in the notification summary.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let mut builder = DefaultNotificationBuilder::new()
+    ///     .set_title("a title")
+    ///     .set_relevance_score(0.5);
+    /// let payload = builder.build("token", Default::default());
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"},\"relevance-score\":0.5}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn set_relevance_score(mut self, score: f64) -> Self {
+        self.relevance_score = Some(score);
+        self
+    }
+}
+
+impl<'a> DefaultNotificationBuilder<'a> {
+    /// Custom data added to the payload under `root_key` when built, like
+    /// [`Payload::add_custom_data`], without leaving the builder chain.
+    ///
+    /// If `data` can't be serialized or `root_key` is `aps`, the entry is
+    /// left out of the payload and [`try_build`](#method.try_build) fails.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// # use std::collections::HashMap;
+    /// # fn main() {
+    /// let mut custom_data = HashMap::new();
+    /// custom_data.insert("foo", "bar");
+    ///
+    /// let payload = DefaultNotificationBuilder::new()
+    ///     .set_title("a title")
+    ///     .set_custom_data("foo_data", &custom_data)
+    ///     .try_build("token", Default::default())
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"alert\":{\"title\":\"a title\"}},\"foo_data\":{\"foo\":\"bar\"}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn set_custom_data<T>(mut self, root_key: &'a str, data: &T) -> Self
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        let value = check_root_key(root_key).and_then(|_| Ok(serde_json::to_value(data)?));
+
+        match value {
+            Ok(value) => {
+                self.custom_data.insert(root_key.into(), value);
+            }
+            Err(e) => {
+                self.custom_data_error.get_or_insert_with(|| e.to_string());
+            }
+        }
+
+        self
+    }
+
+    /// Like [`build`](#method.build), but fails with
+    /// [`Error::InvalidOptions`] if any data given to
+    /// [`set_custom_data`](#method.set_custom_data) was rejected.
+    pub fn try_build(self, device_token: &'a str, options: NotificationOptions<'a>) -> Result<Payload<'a>, Error> {
+        if let Some(ref error) = self.custom_data_error {
+            return Err(Error::InvalidOptions(format!("Invalid custom data: {}", error)));
+        }
+
+        Ok(self.build(device_token, options))
+    }
 }
 
 impl<'a> NotificationBuilder<'a> for DefaultNotificationBuilder<'a> {
-    fn build(self, device_token: &'a str, options: NotificationOptions<'a>) -> Payload<'a> {
-        Payload {
-            aps: APS {
-                alert: match self.has_edited_alert {
-                    true => Some(APSAlert::Default(self.alert)),
-                    false => self.alert.body.map(APSAlert::Body),
-                },
-                badge: self.badge,
-                sound: if self.sound.critical {
-                    Some(APSSound::Critical(self.sound))
-                } else {
-                    self.sound.name.map(APSSound::Sound)
-                },
-                content_available: self.content_available,
-                category: self.category,
-                mutable_content: Some(self.mutable_content),
-                url_args: None,
+    fn build(self, device_token: &'a str, mut options: NotificationOptions<'a>) -> Payload<'a> {
+        let aps = APS {
+            alert: match self.has_edited_alert {
+                true => Some(APSAlert::Default(self.alert)),
+                false => self.alert.body.map(APSAlert::Body),
+            },
+            badge: self.badge,
+            sound: if self.sound.critical || self.sound_dictionary {
+                Some(APSSound::Critical(self.sound))
+            } else {
+                self.sound.name.map(APSSound::Sound)
             },
+            thread_id: self.thread_id.map(Cow::Borrowed),
+            content_available: self.content_available,
+            category: self.category.map(Cow::Borrowed),
+            mutable_content: Some(self.mutable_content).filter(|&flag| flag != 0),
+            url_args: self
+                .url_args
+                .map(|args| args.iter().copied().map(Cow::Borrowed).collect()),
+            target_content_id: self.target_content_id.map(Cow::Borrowed),
+            interruption_level: self.interruption_level,
+            relevance_score: self.relevance_score,
+            ..Default::default()
+        };
+
+        // A payload only waking up the app is a background notification,
+        // anything shown to the user is an alert.
+        let silent =
+            aps.content_available == Some(1) && aps.alert.is_none() && aps.sound.is_none() && aps.badge.is_none();
+
+        options.apns_push_type.get_or_insert(match silent {
+            true => PushType::Background,
+            false => PushType::Alert,
+        });
+
+        Payload {
+            aps,
             device_token,
             options,
-            data: BTreeMap::new(),
+            data: self.custom_data,
         }
     }
 }
@@ -553,6 +956,7 @@ impl<'a> Default for DefaultNotificationBuilder<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::request::payload::PayloadLike;
     use serde_json::value::to_value;
 
     #[test]
@@ -568,13 +972,23 @@ mod tests {
                     "body": "the body",
                     "title": "the title",
                 },
-                "mutable-content": 0
             }
         });
 
         assert_eq!(expected_payload, to_value(payload).unwrap());
     }
 
+    #[test]
+    fn test_mutable_content_absent_by_default() {
+        let payload = DefaultNotificationBuilder::new()
+            .set_title("a title")
+            .build("device-token", Default::default());
+
+        let payload = to_value(payload).unwrap();
+
+        assert_eq!(None, payload["aps"].get("mutable-content"));
+    }
+
     #[test]
     fn test_default_notification_with_full_data() {
         let builder = DefaultNotificationBuilder::new()
@@ -621,6 +1035,228 @@ mod tests {
         assert_eq!(expected_payload, to_value(payload).unwrap());
     }
 
+    #[test]
+    fn test_notification_with_target_scene() {
+        let payload = DefaultNotificationBuilder::new()
+            .set_body("the body")
+            .set_target_scene("window-1")
+            .build("device-token", Default::default());
+
+        let expected_payload = json!({
+            "aps": {
+                "alert": "the body",
+                "target-content-id": "window-1",
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+
+    #[test]
+    fn test_default_notification_with_url_args() {
+        let payload = DefaultNotificationBuilder::new()
+            .set_title("the title")
+            .set_body("the body")
+            .set_badge(2)
+            .set_url_args(&["boarding", "A998"])
+            .set_target_content_id("flight-A998")
+            .build("device-token", Default::default());
+
+        let expected_payload = json!({
+            "aps": {
+                "alert": {
+                    "title": "the title",
+                    "body": "the body",
+                },
+                "badge": 2,
+                "url-args": ["boarding", "A998"],
+                "target-content-id": "flight-A998",
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+
+    #[test]
+    fn test_try_set_critical() {
+        let payload = DefaultNotificationBuilder::new()
+            .try_set_critical(true, Some(0.5))
+            .unwrap()
+            .build("device-token", Default::default());
+
+        let expected_payload = json!({
+            "aps": {
+                "sound": {
+                    "critical": 1,
+                    "volume": 0.5,
+                },
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+        assert!(DefaultNotificationBuilder::new().try_set_critical(false, None).is_ok());
+
+        let result = DefaultNotificationBuilder::new().try_set_critical(false, Some(0.5));
+        assert!(matches!(result, Err(Error::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn test_non_critical_sound_dictionary() {
+        let payload = DefaultNotificationBuilder::new()
+            .set_body("the body")
+            .set_sound_dictionary("ping", Some(0.8))
+            .build("device-token", Default::default());
+
+        let expected_payload = json!({
+            "aps": {
+                "alert": "the body",
+                "sound": {
+                    "name": "ping",
+                    "volume": 0.8,
+                },
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+
+    #[test]
+    fn test_sound_dictionary_without_volume() {
+        let payload = DefaultNotificationBuilder::new()
+            .set_sound_dictionary("ping", None)
+            .build("device-token", Default::default());
+
+        let expected_payload = json!({
+            "aps": {
+                "sound": {
+                    "name": "ping",
+                },
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+
+    #[test]
+    fn test_custom_data_in_builder_chain() {
+        #[derive(Serialize)]
+        struct Conversation {
+            id: u32,
+        }
+
+        let payload = DefaultNotificationBuilder::new()
+            .set_title("the title")
+            .set_body("the body")
+            .set_custom_data("conversation", &Conversation { id: 42 })
+            .set_custom_data("message_id", "abc")
+            .try_build("device-token", Default::default())
+            .unwrap();
+
+        let expected_payload = json!({
+            "aps": {
+                "alert": {
+                    "title": "the title",
+                    "body": "the body",
+                },
+            },
+            "conversation": {
+                "id": 42,
+            },
+            "message_id": "abc",
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+
+    #[test]
+    fn test_custom_data_under_aps_fails_to_build() {
+        let result = DefaultNotificationBuilder::new()
+            .set_body("the body")
+            .set_custom_data("aps", "oops")
+            .try_build("device-token", Default::default());
+
+        assert!(matches!(result, Err(Error::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn test_clear_badge() {
+        let payload = DefaultNotificationBuilder::new()
+            .set_body("the body")
+            .set_badge(4)
+            .clear_badge()
+            .build("device-token", Default::default());
+
+        let expected_payload = json!({
+            "aps": {
+                "alert": "the body",
+                "badge": 0,
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+
+    #[test]
+    fn test_leave_badge_unchanged() {
+        let payload = DefaultNotificationBuilder::new()
+            .set_body("the body")
+            .set_badge(4)
+            .leave_badge_unchanged()
+            .build("device-token", Default::default());
+
+        let expected_payload = json!({
+            "aps": {
+                "alert": "the body",
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+
+    #[test]
+    fn test_empty_loc_args_are_omitted() {
+        let empty: &[&str] = &[];
+
+        let payload = DefaultNotificationBuilder::new()
+            .set_title("the title")
+            .set_loc_args(empty)
+            .set_title_loc_args(empty)
+            .build("device-token", Default::default());
+
+        let expected_payload = json!({
+            "aps": {
+                "alert": {
+                    "title": "the title",
+                },
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+
+    #[test]
+    fn test_empty_loc_args_clear_previous_args() {
+        let empty: &[&str] = &[];
+
+        let payload = DefaultNotificationBuilder::new()
+            .set_title("the title")
+            .set_loc_args(&["narf"])
+            .set_title_loc_args(&["derp"])
+            .set_loc_args(empty)
+            .set_title_loc_args(empty)
+            .build("device-token", Default::default());
+
+        let expected_payload = json!({
+            "aps": {
+                "alert": {
+                    "title": "the title",
+                },
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+
     #[test]
     fn test_notification_with_custom_data_1() {
         #[derive(Serialize, Debug)]
@@ -664,7 +1300,6 @@ mod tests {
                     "body": "the body",
                     "title": "the title",
                 },
-                "mutable-content": 0,
             },
         });
 
@@ -710,7 +1345,6 @@ mod tests {
             },
             "aps": {
                 "alert": "kulli",
-                "mutable-content": 0
             }
         });
 
@@ -726,7 +1360,6 @@ mod tests {
         let expected_payload = json!({
             "aps": {
                 "content-available": 1,
-                "mutable-content": 0
             }
         });
 
@@ -764,7 +1397,6 @@ mod tests {
         let expected_payload = json!({
             "aps": {
                 "content-available": 1,
-                "mutable-content": 0
             },
             "custom": {
                 "key_str": "foo",
@@ -794,7 +1426,6 @@ mod tests {
         let expected_payload = json!({
             "aps": {
                 "content-available": 1,
-                "mutable-content": 0,
             },
             "custom": {
                 "key_str": "foo",
@@ -804,4 +1435,57 @@ mod tests {
 
         assert_eq!(expected_payload, to_value(payload).unwrap());
     }
+
+    #[test]
+    fn test_default_push_type() {
+        let alert = DefaultNotificationBuilder::new()
+            .set_body("Hi there")
+            .build("device-token", Default::default());
+
+        assert_eq!(Some(PushType::Alert), alert.options.apns_push_type);
+
+        let background = DefaultNotificationBuilder::new()
+            .set_content_available()
+            .build("device-token", Default::default());
+
+        assert_eq!(Some(PushType::Background), background.options.apns_push_type);
+
+        let with_badge = DefaultNotificationBuilder::new()
+            .set_content_available()
+            .set_badge(1)
+            .build("device-token", Default::default());
+
+        assert_eq!(Some(PushType::Alert), with_badge.options.apns_push_type);
+
+        let explicit = DefaultNotificationBuilder::new().set_body("Hi there").build(
+            "device-token",
+            NotificationOptions {
+                apns_push_type: Some(PushType::Voip),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(Some(PushType::Voip), explicit.options.apns_push_type);
+    }
+
+    #[test]
+    fn test_example_notification() {
+        let payload = DefaultNotificationBuilder::example().build("device-token", Default::default());
+
+        let compact = payload.to_json_string().unwrap();
+        let pretty = payload.to_json_pretty().unwrap();
+
+        assert!(pretty.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<Value>(&compact).unwrap(),
+            serde_json::from_str::<Value>(&pretty).unwrap()
+        );
+        assert_eq!(pretty, payload.to_json_pretty().unwrap());
+
+        let aps = &serde_json::from_str::<Value>(&compact).unwrap()["aps"];
+
+        assert_eq!(json!("Order shipped"), aps["alert"]["title"]);
+        assert_eq!(json!(1), aps["mutable-content"]);
+        assert_eq!(json!("time-sensitive"), aps["interruption-level"]);
+    }
 }
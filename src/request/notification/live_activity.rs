@@ -0,0 +1,355 @@
+use crate::error::Error;
+use crate::request::notification::{NotificationBuilder, NotificationOptions, PushType};
+use crate::request::payload::{Payload, APS};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::{borrow::Cow, collections::BTreeMap};
+
+/// The action a Live Activity push performs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LiveActivityEvent {
+    /// Starts a new Live Activity. Requires `attributes-type` and `attributes`.
+    Start,
+    /// Updates the content of a running Live Activity.
+    Update,
+    /// Ends a running Live Activity.
+    End,
+}
+
+/// A builder to create an ActivityKit push payload for starting, updating or
+/// ending a Live Activity. The built payload always uses the `liveactivity`
+/// push type.
+///
+/// # Example
+///
+/// ```rust
+/// # use a2::request::notification::{LiveActivityBuilder, LiveActivityEvent, NotificationBuilder};
+/// # use a2::request::payload::PayloadLike;
+/// # use std::collections::HashMap;
+/// # fn main() {
+/// let mut content_state = HashMap::new();
+/// content_state.insert("score", 2);
+///
+/// let payload = LiveActivityBuilder::new(LiveActivityEvent::Update, 1700000000)
+///     .set_content_state(&content_state)
+///     .unwrap()
+///     .set_stale_date(1700003600)
+///     .build("token", Default::default());
+///
+/// assert_eq!(
+///     "{\"aps\":{\"event\":\"update\",\"timestamp\":1700000000,\"content-state\":{\"score\":2},\"stale-date\":1700003600}}",
+///     &payload.to_json_string().unwrap()
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LiveActivityBuilder<'a> {
+    event: LiveActivityEvent,
+    timestamp: u64,
+    content_state: Option<Value>,
+    stale_date: Option<u64>,
+    dismissal_date: Option<u64>,
+    attributes_type: Option<&'a str>,
+    attributes: Option<Value>,
+    filter_criteria: Option<&'a str>,
+}
+
+impl<'a> LiveActivityBuilder<'a> {
+    /// Creates a new builder for the given event, generated at `timestamp`
+    /// (UNIX epoch in seconds).
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{LiveActivityBuilder, LiveActivityEvent, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let payload = LiveActivityBuilder::new(LiveActivityEvent::End, 1700000000)
+    ///     .build("token", Default::default());
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"event\":\"end\",\"timestamp\":1700000000}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn new(event: LiveActivityEvent, timestamp: u64) -> LiveActivityBuilder<'a> {
+        LiveActivityBuilder {
+            event,
+            timestamp,
+            content_state: None,
+            stale_date: None,
+            dismissal_date: None,
+            attributes_type: None,
+            attributes: None,
+            filter_criteria: None,
+        }
+    }
+
+    /// Sets the Live Activity event.
+    pub fn set_event(mut self, event: LiveActivityEvent) -> Self {
+        self.event = event;
+        self
+    }
+
+    /// Sets the UNIX epoch date in seconds when the update was generated. The
+    /// system ignores updates older than the one it already displays.
+    pub fn set_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// The dynamic content of the Live Activity, serialized under the
+    /// `content-state` key. Must match the `ContentState` type of the app's
+    /// `ActivityAttributes`, so mirroring that type in a struct deriving
+    /// `Serialize` keeps the two in sync.
+    ///
+    /// Fails if the content can't be represented as JSON, e.g. a map with
+    /// non-string keys.
+    pub fn set_content_state<T>(mut self, content_state: &T) -> Result<Self, Error>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        self.content_state = Some(serde_json::to_value(content_state)?);
+        Ok(self)
+    }
+
+    /// The UNIX epoch date in seconds after which the content is considered
+    /// outdated.
+    pub fn set_stale_date(mut self, stale_date: u64) -> Self {
+        self.stale_date = Some(stale_date);
+        self
+    }
+
+    /// Like [`set_stale_date`](#method.set_stale_date), from a date. Dates
+    /// before the UNIX epoch are sent as zero.
+    pub fn set_stale_date_utc(self, stale_date: DateTime<Utc>) -> Self {
+        self.set_stale_date(epoch_seconds(stale_date))
+    }
+
+    /// The UNIX epoch date in seconds when an ended Live Activity is removed
+    /// from the Lock Screen.
+    pub fn set_dismissal_date(mut self, dismissal_date: u64) -> Self {
+        self.dismissal_date = Some(dismissal_date);
+        self
+    }
+
+    /// Like [`set_dismissal_date`](#method.set_dismissal_date), from a date.
+    /// Dates before the UNIX epoch are sent as zero.
+    pub fn set_dismissal_date_utc(self, dismissal_date: DateTime<Utc>) -> Self {
+        self.set_dismissal_date(epoch_seconds(dismissal_date))
+    }
+
+    /// The `ActivityAttributes` type name and its static values. Only
+    /// included in the payload for [`LiveActivityEvent::Start`].
+    pub fn set_attributes<T>(mut self, attributes_type: &'a str, attributes: &T) -> Result<Self, Error>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        self.attributes_type = Some(attributes_type);
+        self.attributes = Some(serde_json::to_value(attributes)?);
+        Ok(self)
+    }
+
+    /// Limits the update to the devices whose subscription to the broadcast
+    /// channel matches the criteria. Only valid for broadcast pushes sent
+    /// with [`Client::send_broadcast`](crate::Client::send_broadcast); APNs
+    /// rejects it for pushes to a device token.
+    pub fn set_filter_criteria(mut self, filter_criteria: &'a str) -> Self {
+        self.filter_criteria = Some(filter_criteria);
+        self
+    }
+}
+
+fn epoch_seconds(date: DateTime<Utc>) -> u64 {
+    u64::try_from(date.timestamp()).unwrap_or(0)
+}
+
+impl<'a> NotificationBuilder<'a> for LiveActivityBuilder<'a> {
+    fn build(self, device_token: &'a str, mut options: NotificationOptions<'a>) -> Payload<'a> {
+        options.apns_push_type = Some(PushType::LiveActivity);
+
+        let is_start = self.event == LiveActivityEvent::Start;
+
+        Payload {
+            aps: APS {
+                event: Some(self.event),
+                timestamp: Some(self.timestamp),
+                content_state: self.content_state,
+                stale_date: self.stale_date,
+                dismissal_date: self.dismissal_date,
+                attributes_type: self.attributes_type.filter(|_| is_start).map(Cow::Borrowed),
+                attributes: self.attributes.filter(|_| is_start),
+                filter_criteria: self.filter_criteria.map(Cow::Borrowed),
+                ..Default::default()
+            },
+            device_token,
+            options,
+            data: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::payload::PayloadLike;
+    use serde_json::value::to_value;
+
+    #[derive(Serialize)]
+    struct ContentState {
+        home: u32,
+        away: u32,
+    }
+
+    #[derive(Serialize)]
+    struct Attributes {
+        match_id: &'static str,
+    }
+
+    #[test]
+    fn test_live_activity_start() {
+        let payload = LiveActivityBuilder::new(LiveActivityEvent::Start, 1700000000)
+            .set_content_state(&ContentState { home: 0, away: 0 })
+            .unwrap()
+            .set_attributes("MatchAttributes", &Attributes { match_id: "m1" })
+            .unwrap()
+            .set_stale_date(1700003600)
+            .build("device-token", Default::default());
+
+        assert_eq!(Some(PushType::LiveActivity), payload.get_options().apns_push_type);
+
+        let expected_payload = json!({
+            "aps": {
+                "event": "start",
+                "timestamp": 1700000000,
+                "content-state": {
+                    "home": 0,
+                    "away": 0,
+                },
+                "stale-date": 1700003600,
+                "attributes-type": "MatchAttributes",
+                "attributes": {
+                    "match_id": "m1",
+                },
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+
+    #[test]
+    fn test_live_activity_typed_content_state() {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct DeliveryState<'a> {
+            driver_name: &'a str,
+            minutes_left: Option<u32>,
+        }
+
+        let state = DeliveryState {
+            driver_name: "Anna",
+            minutes_left: Some(12),
+        };
+
+        let payload = LiveActivityBuilder::new(LiveActivityEvent::Update, 1700000000)
+            .set_content_state(&state)
+            .unwrap()
+            .build("device-token", Default::default());
+
+        let expected_payload = json!({
+            "aps": {
+                "event": "update",
+                "timestamp": 1700000000,
+                "content-state": {
+                    "driverName": "Anna",
+                    "minutesLeft": 12,
+                },
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+
+    #[test]
+    fn test_live_activity_filter_criteria() {
+        let payload = LiveActivityBuilder::new(LiveActivityEvent::Update, 1700000000)
+            .set_filter_criteria("team-a")
+            .build("", Default::default());
+
+        let expected_payload = json!({
+            "aps": {
+                "event": "update",
+                "timestamp": 1700000000,
+                "filter-criteria": "team-a",
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+
+    #[test]
+    fn test_live_activity_dates_utc() {
+        let stale_date = DateTime::parse_from_rfc3339("2023-11-14T23:13:20Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let dismissal_date = DateTime::parse_from_rfc3339("2023-11-15T01:13:20Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let payload = LiveActivityBuilder::new(LiveActivityEvent::End, 1700000000)
+            .set_stale_date_utc(stale_date)
+            .set_dismissal_date_utc(dismissal_date)
+            .build("device-token", Default::default());
+
+        assert_eq!(Some(1700003600), payload.aps.stale_date);
+        assert_eq!(Some(1700010800), payload.aps.dismissal_date);
+    }
+
+    #[test]
+    fn test_live_activity_date_before_epoch() {
+        let date = DateTime::parse_from_rfc3339("1969-12-31T23:59:59Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let payload = LiveActivityBuilder::new(LiveActivityEvent::End, 1700000000)
+            .set_dismissal_date_utc(date)
+            .build("device-token", Default::default());
+
+        assert_eq!(Some(0), payload.aps.dismissal_date);
+    }
+
+    #[test]
+    fn test_live_activity_update() {
+        let options = NotificationOptions {
+            apns_push_type: Some(PushType::Alert),
+            apns_topic: Some("com.example.app.push-type.liveactivity"),
+            ..Default::default()
+        };
+
+        let payload = LiveActivityBuilder::new(LiveActivityEvent::Start, 1700000000)
+            .set_event(LiveActivityEvent::Update)
+            .set_timestamp(1700000060)
+            .set_content_state(&ContentState { home: 1, away: 0 })
+            .unwrap()
+            .set_attributes("MatchAttributes", &Attributes { match_id: "m1" })
+            .unwrap()
+            .set_dismissal_date(1700007200)
+            .build("device-token", options);
+
+        assert_eq!(Some(PushType::LiveActivity), payload.get_options().apns_push_type);
+
+        let expected_payload = json!({
+            "aps": {
+                "event": "update",
+                "timestamp": 1700000060,
+                "content-state": {
+                    "home": 1,
+                    "away": 0,
+                },
+                "dismissal-date": 1700007200,
+            }
+        });
+
+        assert_eq!(expected_payload, to_value(payload).unwrap());
+    }
+}
@@ -1,7 +1,9 @@
 use crate::error::Error;
+use chrono::{DateTime, Utc};
 use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CollapseId<'a> {
     pub value: &'a str,
 }
@@ -19,7 +21,8 @@ impl<'a> CollapseId<'a> {
     }
 }
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 /// The apns-push-type header field has the following valid values.
 /// The descriptions below describe when and how to use these values.
 /// Send an apns-push-type header with each push. Recent and upcoming features
@@ -50,6 +53,9 @@ pub enum PushType {
     /// The push type for notifications that provide information about updates to
     /// your application’s push to talk services.
     PushToTalk,
+    /// The push type for notifications that contain update information for a
+    /// watchOS app’s complications.
+    Complication,
 }
 
 impl fmt::Display for PushType {
@@ -63,12 +69,63 @@ impl fmt::Display for PushType {
             PushType::Mdm => "mdm",
             PushType::LiveActivity => "liveactivity",
             PushType::PushToTalk => "pushtotalk",
+            PushType::Complication => "complication",
+        })
+    }
+}
+
+/// The suffix APNs expects after the bundle id in the `apns-topic` of some
+/// push types, e.g. `com.example.app.voip` for VoIP notifications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopicSuffix {
+    /// `.voip`, for [`PushType::Voip`].
+    Voip,
+    /// `.complication`, for [`PushType::Complication`].
+    Complication,
+    /// `.pushkit.fileprovider`, for [`PushType::FileProvider`].
+    FileProvider,
+    /// `.push-type.liveactivity`, for [`PushType::LiveActivity`].
+    LiveActivity,
+    /// `.voip-ptt`, for [`PushType::PushToTalk`].
+    PushToTalk,
+    /// `.location-query`, for [`PushType::Location`].
+    Location,
+}
+
+impl TopicSuffix {
+    /// The push type the suffixed topic is used with.
+    pub fn push_type(self) -> PushType {
+        match self {
+            TopicSuffix::Voip => PushType::Voip,
+            TopicSuffix::Complication => PushType::Complication,
+            TopicSuffix::FileProvider => PushType::FileProvider,
+            TopicSuffix::LiveActivity => PushType::LiveActivity,
+            TopicSuffix::PushToTalk => PushType::PushToTalk,
+            TopicSuffix::Location => PushType::Location,
+        }
+    }
+
+    /// The bundle id with the suffix appended.
+    pub fn topic(self, bundle_id: &str) -> String {
+        format!("{}{}", bundle_id, self)
+    }
+}
+
+impl fmt::Display for TopicSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TopicSuffix::Voip => ".voip",
+            TopicSuffix::Complication => ".complication",
+            TopicSuffix::FileProvider => ".pushkit.fileprovider",
+            TopicSuffix::LiveActivity => ".push-type.liveactivity",
+            TopicSuffix::PushToTalk => ".voip-ptt",
+            TopicSuffix::Location => ".location-query",
         })
     }
 }
 
 /// Headers to specify options to the notification.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct NotificationOptions<'a> {
     /// A canonical UUID that identifies the notification. If there is an error
     /// sending the notification, APNs uses this value to identify the
@@ -89,6 +146,8 @@ pub struct NotificationOptions<'a> {
     /// to deliver the notification the first time. If the value is 0, APNs
     /// treats the notification as if it expires immediately and does not store
     /// the notification or attempt to redeliver it.
+    ///
+    /// See [`expires_at`](#method.expires_at) to set it from a date.
     pub apns_expiration: Option<u64>,
 
     /// The priority of the notification. If `None`, the APNs server sets the priority to High.
@@ -114,10 +173,82 @@ pub struct NotificationOptions<'a> {
     /// user as a single notification. The value of this key must not exceed 64
     /// bytes.
     pub apns_collapse_id: Option<CollapseId<'a>>,
+
+    /// An id for the request, sent as the `apns-request-id` header,
+    /// independently of the `apns_id`. Used by broadcast pushes.
+    pub apns_request_id: Option<&'a str>,
+
+    /// Additional headers sent after the APNs headers, e.g. forwarding
+    /// headers required by a relay in between. Sending fails if one of them
+    /// is `authorization`, `content-type`, `content-length` or starts with
+    /// `apns-`, as those are set by the client.
+    pub extra_headers: Option<Vec<(&'a str, &'a str)>>,
+}
+
+impl<'a> NotificationOptions<'a> {
+    /// Sets [`apns_expiration`](#structfield.apns_expiration) to the given
+    /// date, in seconds since the UNIX epoch.
+    ///
+    /// A date at or before the epoch is sent as 0, which APNs treats as
+    /// expiring immediately: the notification is delivered only if the
+    /// device is reachable right away, and dropped otherwise.
+    ///
+    /// ```
+    /// # use a2::NotificationOptions;
+    /// # use chrono::{TimeZone, Utc};
+    /// let options = NotificationOptions::default().expires_at(Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap());
+    ///
+    /// assert_eq!(Some(1893456000), options.apns_expiration);
+    /// ```
+    pub fn expires_at(mut self, at: DateTime<Utc>) -> Self {
+        self.apns_expiration = Some(u64::try_from(at.timestamp()).unwrap_or(0));
+        self
+    }
+
+    /// Like [`expires_at`](#method.expires_at), with a [`SystemTime`].
+    pub fn expires_at_system_time(mut self, at: SystemTime) -> Self {
+        let seconds = at.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs());
+        self.apns_expiration = Some(seconds.unwrap_or(0));
+        self
+    }
+
+    /// The `apns-topic` for the bundle id with the given suffix, to be set as
+    /// [`apns_topic`](#structfield.apns_topic).
+    ///
+    /// Fails if `apns_push_type` is not the push type of the suffix.
+    ///
+    /// ```
+    /// # use a2::{NotificationOptions, PushType, TopicSuffix};
+    /// let mut options = NotificationOptions {
+    ///     apns_push_type: Some(PushType::Voip),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let topic = options.suffixed_topic("com.example.app", TopicSuffix::Voip)?;
+    /// options.apns_topic = Some(&topic);
+    ///
+    /// assert_eq!(Some("com.example.app.voip"), options.apns_topic);
+    /// # Ok::<(), a2::error::Error>(())
+    /// ```
+    pub fn suffixed_topic(&self, bundle_id: &str, suffix: TopicSuffix) -> Result<String, Error> {
+        if self.apns_push_type != Some(suffix.push_type()) {
+            return Err(Error::InvalidOptions(format!(
+                "The topic suffix {} requires the push type {}",
+                suffix,
+                suffix.push_type()
+            )));
+        }
+
+        Ok(suffix.topic(bundle_id))
+    }
 }
 
 /// The importance how fast to bring the notification for the user..
-#[derive(Debug, Clone)]
+///
+/// Serialized by name, e.g. `"high"`, unlike the numeric `apns-priority`
+/// header value of its `Display` implementation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Priority {
     /// Send the push message immediately. Notifications with this priority must
     /// trigger an alert, sound, or badge on the target device. Cannot be used
@@ -129,6 +260,11 @@ pub enum Priority {
     /// grouped and delivered in bursts. They are throttled, and in some cases
     /// are not delivered.
     Normal,
+
+    /// Prioritize the device's power considerations over all other factors
+    /// for delivery, and prevent awakening the device. Primarily used with
+    /// background pushes.
+    Low,
 }
 
 impl fmt::Display for Priority {
@@ -136,6 +272,7 @@ impl fmt::Display for Priority {
         let priority = match self {
             Priority::High => "10",
             Priority::Normal => "5",
+            Priority::Low => "1",
         };
 
         write!(f, "{}", priority)
@@ -147,6 +284,133 @@ mod tests {
     use super::*;
     use std::str;
 
+    #[test]
+    fn test_options_equality() {
+        let topic = String::from("com.example.app");
+        let collapse_id = String::from("a-collapse-id");
+
+        let options = NotificationOptions {
+            apns_topic: Some("com.example.app"),
+            apns_collapse_id: Some(CollapseId::new("a-collapse-id").unwrap()),
+            apns_priority: Some(Priority::Normal),
+            ..Default::default()
+        };
+        let same_options = NotificationOptions {
+            apns_topic: Some(&topic),
+            apns_collapse_id: Some(CollapseId::new(&collapse_id).unwrap()),
+            apns_priority: Some(Priority::Normal),
+            ..Default::default()
+        };
+
+        assert_eq!(options, same_options);
+        assert_ne!(
+            options,
+            NotificationOptions {
+                apns_priority: Some(Priority::High),
+                ..same_options.clone()
+            }
+        );
+    }
+
+    #[test]
+    fn test_expires_at() {
+        let at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+
+        assert_eq!(
+            Some(1_700_000_000),
+            NotificationOptions::default().expires_at(at).apns_expiration
+        );
+
+        let at = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+
+        assert_eq!(
+            Some(1_700_000_000),
+            NotificationOptions::default()
+                .expires_at_system_time(at)
+                .apns_expiration
+        );
+
+        let before_epoch = DateTime::from_timestamp(-60, 0).unwrap();
+
+        assert_eq!(
+            Some(0),
+            NotificationOptions::default().expires_at(before_epoch).apns_expiration
+        );
+    }
+
+    #[test]
+    fn test_push_type_serde_matches_header_value() {
+        for push_type in [
+            PushType::Alert,
+            PushType::Background,
+            PushType::Location,
+            PushType::Voip,
+            PushType::FileProvider,
+            PushType::Mdm,
+            PushType::LiveActivity,
+            PushType::PushToTalk,
+            PushType::Complication,
+        ] {
+            let json = serde_json::to_value(push_type).unwrap();
+
+            assert_eq!(json!(push_type.to_string()), json);
+            assert_eq!(push_type, serde_json::from_value(json).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_priority_serde() {
+        assert_eq!(json!("high"), serde_json::to_value(Priority::High).unwrap());
+        assert_eq!(json!("normal"), serde_json::to_value(Priority::Normal).unwrap());
+        assert_eq!(json!("low"), serde_json::to_value(Priority::Low).unwrap());
+
+        let priority: Priority = serde_json::from_str(r#""high""#).unwrap();
+        assert!(matches!(priority, Priority::High));
+        assert!(serde_json::from_str::<Priority>(r#""10""#).is_err());
+    }
+
+    #[test]
+    fn test_topic_suffixes() {
+        assert_eq!("com.example.app.voip", TopicSuffix::Voip.topic("com.example.app"));
+        assert_eq!(
+            "com.example.app.complication",
+            TopicSuffix::Complication.topic("com.example.app")
+        );
+        assert_eq!(
+            "com.example.app.pushkit.fileprovider",
+            TopicSuffix::FileProvider.topic("com.example.app")
+        );
+        assert_eq!(
+            "com.example.app.push-type.liveactivity",
+            TopicSuffix::LiveActivity.topic("com.example.app")
+        );
+    }
+
+    #[test]
+    fn test_suffixed_topic_matching_push_type() {
+        let options = NotificationOptions {
+            apns_push_type: Some(PushType::Voip),
+            ..Default::default()
+        };
+
+        let topic = options.suffixed_topic("com.example.app", TopicSuffix::Voip).unwrap();
+        assert_eq!("com.example.app.voip", topic);
+    }
+
+    #[test]
+    fn test_suffixed_topic_wrong_push_type() {
+        let options = NotificationOptions {
+            apns_push_type: Some(PushType::Alert),
+            ..Default::default()
+        };
+        assert!(options.suffixed_topic("com.example.app", TopicSuffix::Voip).is_err());
+
+        let options = NotificationOptions::default();
+        assert!(options
+            .suffixed_topic("com.example.app", TopicSuffix::FileProvider)
+            .is_err());
+    }
+
     #[test]
     fn test_collapse_id_under_64_chars() {
         let collapse_id = CollapseId::new("foo").unwrap();
@@ -0,0 +1,75 @@
+use crate::request::notification::{DefaultAlert, NotificationBuilder, NotificationOptions, PushType};
+use crate::request::payload::{APSAlert, Payload, APS};
+use std::collections::BTreeMap;
+
+/// A builder to create the smallest payload showing an alert with a title
+/// and a body, leaving out every other key.
+///
+/// # Example
+///
+/// ```rust
+/// # use a2::request::notification::{NotificationBuilder, SimpleAlertBuilder};
+/// # use a2::request::payload::PayloadLike;
+/// # fn main() {
+/// let payload = SimpleAlertBuilder::new("Hi there", "What's up?").build("token", Default::default());
+///
+/// assert_eq!(
+///     "{\"aps\":{\"alert\":{\"title\":\"Hi there\",\"body\":\"What's up?\"}}}",
+///     &payload.to_json_string().unwrap()
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SimpleAlertBuilder<'a> {
+    title: &'a str,
+    body: &'a str,
+}
+
+impl<'a> SimpleAlertBuilder<'a> {
+    /// Creates a new builder with the title and body of the alert.
+    pub fn new(title: &'a str, body: &'a str) -> SimpleAlertBuilder<'a> {
+        SimpleAlertBuilder { title, body }
+    }
+}
+
+impl<'a> NotificationBuilder<'a> for SimpleAlertBuilder<'a> {
+    fn build(self, device_token: &'a str, mut options: NotificationOptions<'a>) -> Payload<'a> {
+        options.apns_push_type.get_or_insert(PushType::Alert);
+
+        Payload {
+            aps: APS {
+                alert: Some(APSAlert::Default(DefaultAlert::title_and_body(self.title, self.body))),
+                ..Default::default()
+            },
+            device_token,
+            options,
+            data: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::payload::PayloadLike;
+    use serde_json::Value;
+
+    #[test]
+    fn test_simple_alert_has_no_extra_keys() {
+        let payload = SimpleAlertBuilder::new("a title", "a body")
+            .build("device-token", Default::default())
+            .to_json_string()
+            .unwrap();
+
+        let expected = json!({
+            "aps": {
+                "alert": {
+                    "title": "a title",
+                    "body": "a body",
+                }
+            }
+        });
+
+        assert_eq!(expected, serde_json::from_str::<Value>(&payload).unwrap());
+    }
+}
@@ -1,13 +1,13 @@
-use crate::request::notification::{NotificationBuilder, NotificationOptions};
+use crate::request::notification::{NotificationBuilder, NotificationOptions, PushType};
 use crate::request::payload::{APSAlert, APSSound, Payload, APS};
-use std::collections::BTreeMap;
+use std::{borrow::Cow, collections::BTreeMap};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct WebPushAlert<'a> {
-    pub title: &'a str,
-    pub body: &'a str,
-    pub action: &'a str,
+    pub title: Cow<'a, str>,
+    pub body: Cow<'a, str>,
+    pub action: Cow<'a, str>,
 }
 
 /// A builder to create a simple APNs notification payload.
@@ -18,7 +18,7 @@ pub struct WebPushAlert<'a> {
 /// # use a2::request::notification::{NotificationBuilder, WebNotificationBuilder, WebPushAlert};
 /// # use a2::request::payload::PayloadLike;
 /// # fn main() {
-/// let mut builder = WebNotificationBuilder::new(WebPushAlert {title: "Hello", body: "World", action: "View"}, &["arg1"]);
+/// let mut builder = WebNotificationBuilder::new(WebPushAlert {title: "Hello".into(), body: "World".into(), action: "View".into()}, &["arg1"]);
 /// builder.set_sound("prööt");
 /// let payload = builder.build("device_id", Default::default())
 ///    .to_json_string().unwrap();
@@ -27,7 +27,8 @@ pub struct WebPushAlert<'a> {
 pub struct WebNotificationBuilder<'a> {
     alert: WebPushAlert<'a>,
     sound: Option<&'a str>,
-    url_args: &'a [&'a str],
+    thread_id: Option<&'a str>,
+    url_args: Option<&'a [&'a str]>,
 }
 
 impl<'a> WebNotificationBuilder<'a> {
@@ -37,7 +38,7 @@ impl<'a> WebNotificationBuilder<'a> {
     /// # use a2::request::notification::{WebNotificationBuilder, NotificationBuilder, WebPushAlert};
     /// # use a2::request::payload::PayloadLike;
     /// # fn main() {
-    /// let mut builder = WebNotificationBuilder::new(WebPushAlert {title: "Hello", body: "World", action: "View"}, &["arg1"]);
+    /// let mut builder = WebNotificationBuilder::new(WebPushAlert {title: "Hello".into(), body: "World".into(), action: "View".into()}, &["arg1"]);
     /// let payload = builder.build("token", Default::default());
     ///
     /// assert_eq!(
@@ -50,17 +51,49 @@ impl<'a> WebNotificationBuilder<'a> {
         WebNotificationBuilder {
             alert,
             sound: None,
-            url_args,
+            thread_id: None,
+            url_args: Some(url_args),
         }
     }
 
+    /// Creates a new builder without `url-args`, for websites that don't
+    /// format their URLs with arguments.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{WebNotificationBuilder, NotificationBuilder, WebPushAlert};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let builder = WebNotificationBuilder::without_url_args(WebPushAlert {title: "Hello".into(), body: "World".into(), action: "View".into()});
+    /// let payload = builder.build("token", Default::default());
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"alert\":{\"title\":\"Hello\",\"body\":\"World\",\"action\":\"View\"}}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn without_url_args(alert: WebPushAlert<'a>) -> WebNotificationBuilder<'a> {
+        WebNotificationBuilder {
+            alert,
+            sound: None,
+            thread_id: None,
+            url_args: None,
+        }
+    }
+
+    /// The arguments filled into the URL format string of the website.
+    pub fn set_url_args(&mut self, url_args: &'a [&'a str]) -> &mut Self {
+        self.url_args = Some(url_args);
+        self
+    }
+
     /// File name of the custom sound to play when receiving the notification.
     ///
     /// ```rust
     /// # use a2::request::notification::{WebNotificationBuilder, NotificationBuilder, WebPushAlert};
     /// # use a2::request::payload::PayloadLike;
     /// # fn main() {
-    /// let mut builder = WebNotificationBuilder::new(WebPushAlert {title: "Hello", body: "World", action: "View"}, &["arg1"]);
+    /// let mut builder = WebNotificationBuilder::new(WebPushAlert {title: "Hello".into(), body: "World".into(), action: "View".into()}, &["arg1"]);
     /// builder.set_sound("meow");
     /// let payload = builder.build("token", Default::default());
     ///
@@ -74,19 +107,46 @@ impl<'a> WebNotificationBuilder<'a> {
         self.sound = Some(sound);
         self
     }
+
+    /// An identifier to group related notifications together.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{WebNotificationBuilder, NotificationBuilder, WebPushAlert};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let mut builder = WebNotificationBuilder::new(WebPushAlert {title: "Hello".into(), body: "World".into(), action: "View".into()}, &["arg1"]);
+    /// builder.set_thread_id("news");
+    /// let payload = builder.build("token", Default::default());
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"alert\":{\"title\":\"Hello\",\"body\":\"World\",\"action\":\"View\"},\"thread-id\":\"news\",\"url-args\":[\"arg1\"]}}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn set_thread_id(&mut self, thread_id: &'a str) -> &mut Self {
+        self.thread_id = Some(thread_id);
+        self
+    }
 }
 
 impl<'a> NotificationBuilder<'a> for WebNotificationBuilder<'a> {
-    fn build(self, device_token: &'a str, options: NotificationOptions<'a>) -> Payload<'a> {
+    fn build(self, device_token: &'a str, mut options: NotificationOptions<'a>) -> Payload<'a> {
+        options.apns_push_type.get_or_insert(PushType::Alert);
+
         Payload {
             aps: APS {
                 alert: Some(APSAlert::WebPush(self.alert)),
                 badge: None,
-                sound: self.sound.map(APSSound::Sound),
+                sound: self.sound.map(|sound| APSSound::Sound(sound.into())),
+                thread_id: self.thread_id.map(Cow::Borrowed),
                 content_available: None,
                 category: None,
                 mutable_content: None,
-                url_args: Some(self.url_args),
+                url_args: self
+                    .url_args
+                    .map(|args| args.iter().copied().map(Cow::Borrowed).collect()),
+                ..Default::default()
             },
             device_token,
             options,
@@ -105,9 +165,9 @@ mod tests {
     fn test_webpush_notification() {
         let payload = WebNotificationBuilder::new(
             WebPushAlert {
-                action: "View",
-                title: "Hello",
-                body: "world",
+                action: "View".into(),
+                title: "Hello".into(),
+                body: "world".into(),
             },
             &["arg1"],
         )
@@ -128,4 +188,88 @@ mod tests {
 
         assert_eq!(expected_payload, serde_json::from_str::<Value>(&payload).unwrap());
     }
+
+    #[test]
+    fn test_webpush_notification_without_url_args() {
+        let payload = WebNotificationBuilder::without_url_args(WebPushAlert {
+            action: "View".into(),
+            title: "Hello".into(),
+            body: "world".into(),
+        })
+        .build("device-token", Default::default())
+        .to_json_string()
+        .unwrap();
+
+        let expected_payload = json!({
+            "aps": {
+                "alert": {
+                    "title": "Hello",
+                    "body": "world",
+                    "action": "View",
+                },
+            }
+        });
+
+        assert_eq!(expected_payload, serde_json::from_str::<Value>(&payload).unwrap());
+    }
+
+    #[test]
+    fn test_webpush_notification_set_url_args() {
+        let mut builder = WebNotificationBuilder::without_url_args(WebPushAlert {
+            action: "View".into(),
+            title: "Hello".into(),
+            body: "world".into(),
+        });
+        builder.set_url_args(&["arg1", "arg2"]);
+
+        let payload = builder
+            .build("device-token", Default::default())
+            .to_json_string()
+            .unwrap();
+
+        let expected_payload = json!({
+            "aps": {
+                "alert": {
+                    "title": "Hello",
+                    "body": "world",
+                    "action": "View",
+                },
+                "url-args": ["arg1", "arg2"]
+            }
+        });
+
+        assert_eq!(expected_payload, serde_json::from_str::<Value>(&payload).unwrap());
+    }
+
+    #[test]
+    fn test_webpush_notification_with_thread_id() {
+        let mut builder = WebNotificationBuilder::new(
+            WebPushAlert {
+                action: "View".into(),
+                title: "Hello".into(),
+                body: "world".into(),
+            },
+            &["arg1"],
+        );
+        builder.set_thread_id("news");
+
+        let payload = builder
+            .build("device-token", Default::default())
+            .to_json_string()
+            .unwrap();
+
+        let expected_payload = json!({
+            "aps": {
+                "alert": {
+                    "title": "Hello",
+                    "body": "world",
+                    "action": "View",
+                },
+                "thread-id": "news",
+                "url-args": ["arg1"]
+            }
+        });
+
+        assert_eq!(expected_payload, serde_json::from_str::<Value>(&payload).unwrap());
+    }
 }
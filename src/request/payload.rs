@@ -1,13 +1,21 @@
 /// Payload with `aps` and custom data
 use crate::error::Error;
-use crate::request::notification::{DefaultAlert, DefaultSound, NotificationOptions, WebPushAlert};
+use crate::request::notification::{
+    CollapseId, DefaultAlert, DefaultSound, InterruptionLevel, LiveActivityEvent, NotificationOptions, Priority,
+    PushType, WebPushAlert,
+};
 use erased_serde::Serialize;
 use serde_json::{self, Value};
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 
 /// The data and options for a push notification.
-#[derive(Debug, Clone, Serialize)]
+///
+/// Deserializing a payload, e.g. one persisted in a queue, restores the `aps`
+/// and custom data, copying the strings out of the JSON input. The options
+/// and the device token are not part of the JSON and are left empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Payload<'a> {
     /// Send options
     #[serde(skip)]
@@ -19,7 +27,7 @@ pub struct Payload<'a> {
     pub aps: APS<'a>,
     /// Application specific payload
     #[serde(flatten)]
-    pub data: BTreeMap<&'a str, Value>,
+    pub data: BTreeMap<Cow<'a, str>, Value>,
 }
 
 /// Object that can be serialized to create an APNS request.
@@ -34,6 +42,8 @@ pub struct Payload<'a> {
 /// use std::fs::File;
 ///
 /// async fn send() -> Result<(), Box<dyn std::error::Error>> {
+/// # #[cfg(any(feature = "jwt-openssl", feature = "jwt-ring"))]
+/// # {
 ///     let builder = DefaultNotificationBuilder::new()
 ///         .set_body("Hi there")
 ///         .set_badge(420)
@@ -47,6 +57,7 @@ pub struct Payload<'a> {
 ///
 ///     let response = client.send(payload).await?;
 ///     println!("Sent: {:?}", response);
+/// # }
 ///     Ok(())
 /// }
 ///
@@ -64,7 +75,7 @@ pub struct Payload<'a> {
 ///     fn get_device_token(&self) -> &'a str {
 ///         self.device_token
 ///     }
-///     fn get_options(&self) -> &NotificationOptions {
+///     fn get_options(&self) -> &NotificationOptions<'_> {
 ///         &self.options
 ///     }
 /// }
@@ -77,11 +88,25 @@ pub trait PayloadLike: serde::Serialize + Debug {
         Ok(serde_json::to_string(&self)?)
     }
 
+    /// Serializes the payload like [`to_json_string`](#method.to_json_string),
+    /// directly into the bytes of the request body.
+    fn to_json_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(serde_json::to_vec(&self)?)
+    }
+
+    /// Serializes the payload like [`to_json_string`](#method.to_json_string),
+    /// pretty-printed, e.g. for documentation. The keys are in a stable
+    /// order, the `aps` fields in their declaration order and the custom
+    /// data sorted by key.
+    fn to_json_pretty(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(&self)?)
+    }
+
     /// Returns token for the device
     fn get_device_token(&self) -> &str;
 
     /// Gets [`NotificationOptions`] for this Payload.
-    fn get_options(&self) -> &NotificationOptions;
+    fn get_options(&self) -> &NotificationOptions<'_>;
 }
 
 impl<'a> PayloadLike for Payload<'a> {
@@ -89,7 +114,7 @@ impl<'a> PayloadLike for Payload<'a> {
         self.device_token
     }
 
-    fn get_options(&self) -> &NotificationOptions {
+    fn get_options(&self) -> &NotificationOptions<'_> {
         &self.options
     }
 }
@@ -102,6 +127,8 @@ impl<'a> Payload<'a> {
     /// collection or if needing more strict type definitions, any struct
     /// that has `#[derive(Serialize)]` from [Serde](https://serde.rs).
     ///
+    /// Fails with [`Error::InvalidOptions`] if `root_key` is `aps`.
+    ///
     /// Using a `HashMap`:
     ///
     /// ```rust
@@ -118,7 +145,7 @@ impl<'a> Payload<'a> {
     /// payload.add_custom_data("foo_data", &custom_data).unwrap();
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"content-available\":1,\"mutable-content\":0},\"foo_data\":{\"foo\":\"bar\"}}",
+    ///     "{\"aps\":{\"content-available\":1},\"foo_data\":{\"foo\":\"bar\"}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// # }
@@ -144,20 +171,273 @@ impl<'a> Payload<'a> {
     /// payload.add_custom_data("foo_data", &custom_data).unwrap();
     ///
     /// assert_eq!(
-    ///     "{\"aps\":{\"content-available\":1,\"mutable-content\":0},\"foo_data\":{\"foo\":\"bar\"}}",
+    ///     "{\"aps\":{\"content-available\":1},\"foo_data\":{\"foo\":\"bar\"}}",
     ///     &payload.to_json_string().unwrap()
     /// );
     /// }
     /// ```
     pub fn add_custom_data(&mut self, root_key: &'a str, data: &dyn Serialize) -> Result<&mut Self, Error> {
-        self.data.insert(root_key, serde_json::to_value(data)?);
+        check_root_key(root_key)?;
+        self.data.insert(root_key.into(), serde_json::to_value(data)?);
 
         Ok(self)
     }
+
+    /// Adds every key and value as a top-level field of the payload, next to
+    /// `aps`, without nesting them under a common key.
+    ///
+    /// Fails with [`Error::InvalidOptions`] if one of the keys is `aps`, in
+    /// which case no field is added.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::request::payload::PayloadLike;
+    /// # fn main() {
+    /// let mut payload = DefaultNotificationBuilder::new()
+    ///     .set_content_available()
+    ///     .build("token", Default::default());
+    ///
+    /// payload.add_custom_data_flat([("message_id", "abc"), ("sender", "bob")]).unwrap();
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"content-available\":1},\"message_id\":\"abc\",\"sender\":\"bob\"}",
+    ///     &payload.to_json_string().unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn add_custom_data_flat<I, V>(&mut self, fields: I) -> Result<&mut Self, Error>
+    where
+        I: IntoIterator<Item = (&'a str, V)>,
+        V: serde::Serialize,
+    {
+        let fields = fields
+            .into_iter()
+            .map(|(key, value)| {
+                check_root_key(key)?;
+                Ok((key.into(), serde_json::to_value(value)?))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        self.data.extend(fields);
+
+        Ok(self)
+    }
+
+    /// A hash of the JSON content, equal for payloads showing the same
+    /// notification, e.g. to skip duplicates generated by several services
+    /// for the same collapse id.
+    ///
+    /// The hash ignores the `options`, the device token and the volatile
+    /// Live Activity `timestamp`. The keys are hashed in sorted order with
+    /// FNV-1a, so the hash is stable across processes and releases.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// let first = DefaultNotificationBuilder::new().set_body("Hi").build("token", Default::default());
+    /// let second = DefaultNotificationBuilder::new().set_body("Hi").build("other-token", Default::default());
+    ///
+    /// assert_eq!(first.content_hash(), second.content_hash());
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        content_hash(self)
+    }
+
+    /// Copies the payload into an [`OwnedPayload`], which doesn't borrow
+    /// and can be queued or moved into another task.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::request::payload::{OwnedPayload, PayloadLike};
+    /// fn queued(device_token: String, body: String) -> OwnedPayload {
+    ///     DefaultNotificationBuilder::new()
+    ///         .set_body(&body)
+    ///         .build(&device_token, Default::default())
+    ///         .into_owned()
+    ///         .unwrap()
+    /// }
+    ///
+    /// let payload = queued(String::from("token"), String::from("Hi there"));
+    ///
+    /// assert_eq!(
+    ///     "{\"aps\":{\"alert\":\"Hi there\"}}",
+    ///     &payload.as_payload().to_json_string().unwrap()
+    /// );
+    /// ```
+    pub fn into_owned(self) -> Result<OwnedPayload, Error> {
+        let body = serde_json::to_value(&self)?;
+        let options = self.options;
+
+        Ok(OwnedPayload {
+            device_token: self.device_token.to_string(),
+            apns_id: options.apns_id.map(String::from),
+            apns_push_type: options.apns_push_type,
+            apns_expiration: options.apns_expiration,
+            apns_priority: options.apns_priority,
+            apns_topic: options.apns_topic.map(String::from),
+            apns_collapse_id: options.apns_collapse_id.map(|id| id.value.to_string()),
+            apns_request_id: options.apns_request_id.map(String::from),
+            extra_headers: options.extra_headers.map(|headers| {
+                headers
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect()
+            }),
+            body,
+        })
+    }
+
+    /// Checks that the `aps` content fits the `apns_push_type`, catching
+    /// mistakes APNs would reject with an opaque HTTP 400:
+    ///
+    /// - A [`PushType::Background`] notification must set `content-available`
+    ///   to one, must not have an alert, sound or badge and must not use
+    ///   [`Priority::High`]. Without a priority the client sends
+    ///   [`Priority::Normal`].
+    /// - A [`PushType::Voip`] notification must not have an alert.
+    ///
+    /// Returns [`Error::InvalidOptions`] describing the first violation.
+    /// [`Client::send`](crate::Client::send) does not call this.
+    ///
+    /// ```rust
+    /// # use a2::request::notification::{DefaultNotificationBuilder, NotificationBuilder};
+    /// # use a2::{NotificationOptions, Priority, PushType};
+    /// let options = NotificationOptions {
+    ///     apns_push_type: Some(PushType::Background),
+    ///     apns_priority: Some(Priority::Normal),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let payload = DefaultNotificationBuilder::new()
+    ///     .set_content_available()
+    ///     .build("token", options);
+    ///
+    /// assert!(payload.validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |message: &str| Err(Error::InvalidOptions(message.to_string()));
+
+        match self.options.apns_push_type {
+            Some(PushType::Background) => {
+                if self.aps.content_available != Some(1) {
+                    return invalid("A background notification must set content-available to 1.");
+                }
+                if self.aps.alert.is_some() || self.aps.sound.is_some() || self.aps.badge.is_some() {
+                    return invalid("A background notification must not have an alert, sound or badge.");
+                }
+                if self.options.apns_priority == Some(Priority::High) {
+                    return invalid("A background notification must use priority 5.");
+                }
+            }
+            Some(PushType::Voip) if self.aps.alert.is_some() => {
+                return invalid("A VoIP notification must not have an alert.");
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// A [`Payload`] owning its data, created with [`Payload::into_owned`].
+///
+/// The options can't be borrowed from the payload itself, so it is sent
+/// through the [`PayloadRef`] returned by [`as_payload`](#method.as_payload).
+#[derive(Debug, Clone)]
+pub struct OwnedPayload {
+    device_token: String,
+    apns_id: Option<String>,
+    apns_push_type: Option<PushType>,
+    apns_expiration: Option<u64>,
+    apns_priority: Option<Priority>,
+    apns_topic: Option<String>,
+    apns_collapse_id: Option<String>,
+    apns_request_id: Option<String>,
+    extra_headers: Option<Vec<(String, String)>>,
+    body: Value,
+}
+
+impl OwnedPayload {
+    /// The token for the receiving device.
+    pub fn device_token(&self) -> &str {
+        &self.device_token
+    }
+
+    /// The payload to pass to [`Client::send`](crate::Client::send),
+    /// borrowing from this one.
+    pub fn as_payload(&self) -> PayloadRef<'_> {
+        PayloadRef {
+            device_token: &self.device_token,
+            options: NotificationOptions {
+                apns_id: self.apns_id.as_deref(),
+                apns_push_type: self.apns_push_type,
+                apns_expiration: self.apns_expiration,
+                apns_priority: self.apns_priority.clone(),
+                apns_topic: self.apns_topic.as_deref(),
+                apns_collapse_id: self.apns_collapse_id.as_deref().map(|value| CollapseId { value }),
+                apns_request_id: self.apns_request_id.as_deref(),
+                extra_headers: self.extra_headers.as_ref().map(|headers| {
+                    headers
+                        .iter()
+                        .map(|(name, value)| (name.as_str(), value.as_str()))
+                        .collect()
+                }),
+            },
+            body: &self.body,
+        }
+    }
+}
+
+/// An [`OwnedPayload`] ready to be sent, see [`OwnedPayload::as_payload`].
+#[derive(Debug, Clone)]
+pub struct PayloadRef<'a> {
+    device_token: &'a str,
+    options: NotificationOptions<'a>,
+    body: &'a Value,
+}
+
+impl serde::Serialize for PayloadRef<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.body.serialize(serializer)
+    }
+}
+
+impl<'a> PayloadLike for PayloadRef<'a> {
+    fn get_device_token(&self) -> &'a str {
+        self.device_token
+    }
+
+    fn get_options(&self) -> &NotificationOptions<'_> {
+        &self.options
+    }
+}
+
+/// Custom data must not replace the `aps` dictionary.
+pub(crate) fn check_root_key(key: &str) -> Result<(), Error> {
+    if key == "aps" {
+        return Err(Error::InvalidOptions(String::from(
+            "Custom data can't use the reserved key aps.",
+        )));
+    }
+
+    Ok(())
+}
+
+/// The hash of [`Payload::content_hash`], for any payload.
+pub(crate) fn content_hash<T: serde::Serialize>(payload: &T) -> u64 {
+    // Can't fail, every key of the payload is a string.
+    let mut content = serde_json::to_value(payload).unwrap_or(Value::Null);
+
+    if let Some(aps) = content.get_mut("aps").and_then(Value::as_object_mut) {
+        aps.remove("timestamp");
+    }
+
+    content.to_string().bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
 }
 
 /// The pre-defined notification data.
-#[derive(Serialize, Default, Debug, Clone)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 #[allow(clippy::upper_case_acronyms)]
 pub struct APS<'a> {
@@ -173,6 +453,10 @@ pub struct APS<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sound: Option<APSSound<'a>>,
 
+    /// An identifier to group related notifications together.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<Cow<'a, str>>,
+
     /// Set to one for silent notifications.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content_available: Option<u8>,
@@ -180,7 +464,7 @@ pub struct APS<'a> {
     /// When a notification includes the category key, the system displays the
     /// actions for that category as buttons in the banner or alert interface.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub category: Option<&'a str>,
+    pub category: Option<Cow<'a, str>>,
 
     /// If set to one, the app can change the notification content before
     /// displaying it to the user.
@@ -188,27 +472,437 @@ pub struct APS<'a> {
     pub mutable_content: Option<u8>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub url_args: Option<&'a [&'a str]>,
+    pub url_args: Option<Vec<Cow<'a, str>>>,
+
+    /// The identifier of the window brought forward when the user opens the
+    /// notification.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_content_id: Option<Cow<'a, str>>,
+
+    /// The importance and delivery timing of the notification.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interruption_level: Option<InterruptionLevel>,
+
+    /// A score between 0 and 1 used to sort notifications in the summary.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relevance_score: Option<f64>,
+
+    /// The Live Activity event: `start`, `update` or `end`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<LiveActivityEvent>,
+
+    /// A UNIX epoch date expressed in seconds (UTC) when the Live Activity
+    /// update was generated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+
+    /// The updated dynamic content of the Live Activity.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_state: Option<Value>,
+
+    /// A UNIX epoch date expressed in seconds (UTC) after which the system
+    /// considers the Live Activity content outdated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale_date: Option<u64>,
+
+    /// A UNIX epoch date expressed in seconds (UTC) when an ended Live
+    /// Activity is removed from the Lock Screen.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dismissal_date: Option<u64>,
+
+    /// The type name of the `ActivityAttributes` used to start a Live Activity.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attributes_type: Option<Cow<'a, str>>,
+
+    /// The static attributes used to start a Live Activity.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attributes: Option<Value>,
+
+    /// Lets devices subscribed to a broadcast channel receive only a subset
+    /// of its Live Activity updates. Only valid for broadcast pushes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_criteria: Option<Cow<'a, str>>,
+}
+
+impl<'a> APS<'a> {
+    /// Every key the `aps` dictionary can hold, mapped to a marker of its
+    /// value type. Unlike the serialized payload, no field is omitted, which
+    /// makes this useful for generating documentation or schemas.
+    ///
+    /// ```rust
+    /// # use a2::request::payload::APS;
+    /// let schema = APS::schema_json();
+    /// assert_eq!("integer", schema["badge"]);
+    /// ```
+    pub fn schema_json() -> Value {
+        json!({
+            "alert": "string | object",
+            "badge": "integer",
+            "sound": "string | object",
+            "thread-id": "string",
+            "content-available": "integer",
+            "category": "string",
+            "mutable-content": "integer",
+            "url-args": "array<string>",
+            "target-content-id": "string",
+            "interruption-level": "string",
+            "relevance-score": "number",
+            "event": "string",
+            "timestamp": "integer",
+            "content-state": "object",
+            "stale-date": "integer",
+            "dismissal-date": "integer",
+            "attributes-type": "string",
+            "attributes": "object",
+            "filter-criteria": "string",
+        })
+    }
 }
 
 /// Different notification content types.
-#[derive(Serialize, Debug, Clone)]
+///
+/// When deserializing, an alert with a title, a body and an action is read
+/// as a web push alert.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum APSAlert<'a> {
-    /// A notification that supports all of the iOS features
-    Default(DefaultAlert<'a>),
     /// Safari web push notification
     WebPush(WebPushAlert<'a>),
+    /// A notification that supports all of the iOS features
+    Default(DefaultAlert<'a>),
     /// A notification with just a body
-    Body(&'a str),
+    Body(Cow<'a, str>),
 }
 
 /// Different notification sound types.
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum APSSound<'a> {
-    /// A critical notification (supported only on >= iOS 12)
+    /// A sound dictionary, used by critical notifications (supported only on
+    /// >= iOS 12) or a sound with a volume
     Critical(DefaultSound<'a>),
     /// Name for a notification sound
-    Sound(&'a str),
+    Sound(Cow<'a, str>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::notification::{
+        DefaultNotificationBuilder, LiveActivityBuilder, LiveActivityEvent, NotificationBuilder, WebNotificationBuilder,
+    };
+
+    #[test]
+    fn test_json_bytes_match_json_string() {
+        let mut payload = DefaultNotificationBuilder::new()
+            .set_title("Hi there")
+            .set_body("What's up?")
+            .set_badge(3)
+            .build("device-token", Default::default());
+
+        payload.add_custom_data("custom", &json!({"key": "value"})).unwrap();
+
+        assert_eq!(
+            payload.to_json_string().unwrap().into_bytes(),
+            payload.to_json_bytes().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let payload = |body, token| {
+            let mut payload = DefaultNotificationBuilder::new()
+                .set_title("Hi there")
+                .set_body(body)
+                .build(token, Default::default());
+            payload.add_custom_data("foo", &"bar").unwrap();
+            payload
+        };
+
+        let options = NotificationOptions {
+            apns_id: Some("an-apns-id"),
+            ..Default::default()
+        };
+        let mut with_options = payload("What's up?", "another-token");
+        with_options.options = options;
+
+        assert_eq!(
+            payload("What's up?", "a-token").content_hash(),
+            with_options.content_hash()
+        );
+        assert_ne!(
+            payload("What's up?", "a-token").content_hash(),
+            payload("What's down?", "a-token").content_hash()
+        );
+    }
+
+    #[test]
+    fn test_content_hash_ignores_live_activity_timestamp() {
+        let update = |timestamp, score| {
+            LiveActivityBuilder::new(LiveActivityEvent::Update, timestamp)
+                .set_content_state(&json!({ "score": score }))
+                .unwrap()
+                .build("token", Default::default())
+        };
+
+        assert_eq!(
+            update(1700000000, 1).content_hash(),
+            update(1700000060, 1).content_hash()
+        );
+        assert_ne!(
+            update(1700000000, 1).content_hash(),
+            update(1700000000, 2).content_hash()
+        );
+    }
+
+    #[test]
+    fn test_into_owned() {
+        let (device_token, topic, collapse_id) = (
+            String::from("a-device-token"),
+            String::from("com.example.app"),
+            String::from("a-collapse-id"),
+        );
+
+        let options = NotificationOptions {
+            apns_topic: Some(&topic),
+            apns_collapse_id: Some(CollapseId::new(&collapse_id).unwrap()),
+            apns_priority: Some(Priority::Normal),
+            ..Default::default()
+        };
+        let mut payload = DefaultNotificationBuilder::new()
+            .set_body("Hi there")
+            .build(&device_token, options);
+        payload.add_custom_data("foo", &"bar").unwrap();
+
+        let expected = payload.to_json_string().unwrap();
+        let owned = payload.into_owned().unwrap();
+        drop((device_token, topic, collapse_id));
+
+        let payload = owned.as_payload();
+
+        assert_eq!(expected, payload.to_json_string().unwrap());
+        assert_eq!("a-device-token", payload.get_device_token());
+        assert_eq!(Some("com.example.app"), payload.get_options().apns_topic);
+        assert_eq!(
+            Some("a-collapse-id"),
+            payload.get_options().apns_collapse_id.as_ref().map(|id| id.value)
+        );
+        assert!(matches!(payload.get_options().apns_priority, Some(Priority::Normal)));
+    }
+
+    #[test]
+    fn test_add_custom_data_rejects_aps() {
+        let mut payload = DefaultNotificationBuilder::new().build("device-token", Default::default());
+        let result = payload.add_custom_data("aps", &json!({"alert": "oops"}));
+
+        assert!(matches!(result, Err(Error::InvalidOptions(_))));
+        assert!(payload.data.is_empty());
+    }
+
+    #[test]
+    fn test_add_custom_data_flat() {
+        let mut payload = DefaultNotificationBuilder::new()
+            .set_body("Hello")
+            .build("device-token", Default::default());
+
+        payload.add_custom_data_flat([("message_id", "abc")]).unwrap();
+
+        assert_eq!(
+            json!({
+                "aps": {
+                    "alert": "Hello",
+                },
+                "message_id": "abc",
+            }),
+            serde_json::to_value(&payload).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_add_custom_data_flat_rejects_aps() {
+        let mut payload = DefaultNotificationBuilder::new().build("device-token", Default::default());
+        let result = payload.add_custom_data_flat([("message_id", "abc"), ("aps", "oops")]);
+
+        assert!(matches!(result, Err(Error::InvalidOptions(_))));
+        assert!(payload.data.is_empty());
+    }
+
+    #[test]
+    fn test_payload_deserialization_round_trip() {
+        let mut payload = DefaultNotificationBuilder::new()
+            .set_title("Hi there")
+            .set_subtitle("From bob")
+            .set_body("What's up?")
+            .set_badge(420)
+            .set_category("cat1")
+            .set_thread_id("chat-42")
+            .set_sound("ping.flac")
+            .set_critical(true, Some(0.5))
+            .set_loc_args(&["argh", "narf"])
+            .set_mutable_content()
+            .set_interruption_level(InterruptionLevel::TimeSensitive)
+            .build("device-token", Default::default());
+
+        payload.add_custom_data("custom", &json!({"key": [1, 2, 3]})).unwrap();
+
+        let json = payload.to_json_string().unwrap();
+        let deserialized: Payload = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&payload.aps).unwrap(),
+            serde_json::to_value(&deserialized.aps).unwrap()
+        );
+        assert_eq!(payload.data, deserialized.data);
+        assert_eq!(json, deserialized.to_json_string().unwrap());
+        assert_eq!("", deserialized.device_token);
+    }
+
+    #[test]
+    fn test_payload_deserialization_with_escapes() {
+        let mut payload = DefaultNotificationBuilder::new()
+            .set_body("He said \"hi\"\nbye \\o/")
+            .build("device-token", Default::default());
+
+        payload.add_custom_data("say \"hi\"", &"back\\slash").unwrap();
+
+        let json = payload.to_json_string().unwrap();
+        let deserialized: Payload = serde_json::from_str(&json).unwrap();
+
+        assert!(matches!(deserialized.aps.alert, Some(APSAlert::Body(ref body)) if body == "He said \"hi\"\nbye \\o/"));
+        assert_eq!(Some(&json!("back\\slash")), deserialized.data.get("say \"hi\""));
+        assert_eq!(json, deserialized.to_json_string().unwrap());
+    }
+
+    #[test]
+    fn test_web_payload_deserialization_round_trip() {
+        let alert = WebPushAlert {
+            title: "Hello".into(),
+            body: "World".into(),
+            action: "View".into(),
+        };
+        let payload = WebNotificationBuilder::new(alert, &["arg1"]).build("device-token", Default::default());
+
+        let json = payload.to_json_string().unwrap();
+        let deserialized: Payload = serde_json::from_str(&json).unwrap();
+
+        assert!(matches!(deserialized.aps.alert, Some(APSAlert::WebPush(_))));
+        assert_eq!(json, deserialized.to_json_string().unwrap());
+    }
+
+    fn background_options<'a>() -> NotificationOptions<'a> {
+        NotificationOptions {
+            apns_push_type: Some(PushType::Background),
+            apns_priority: Some(Priority::Normal),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_background_push() {
+        let payload = DefaultNotificationBuilder::new()
+            .set_content_available()
+            .build("token", background_options());
+
+        assert!(payload.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_background_push_with_alert() {
+        let payload = DefaultNotificationBuilder::new()
+            .set_content_available()
+            .set_body("Hello")
+            .build("token", background_options());
+
+        assert!(matches!(payload.validate(), Err(Error::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn test_validate_background_push_without_content_available() {
+        let payload = DefaultNotificationBuilder::new().build("token", background_options());
+
+        assert!(matches!(payload.validate(), Err(Error::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn test_validate_background_push_with_default_priority() {
+        let options = NotificationOptions {
+            apns_push_type: Some(PushType::Background),
+            ..Default::default()
+        };
+        let payload = DefaultNotificationBuilder::new()
+            .set_content_available()
+            .build("token", options);
+
+        assert!(payload.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_background_push_with_high_priority() {
+        let options = NotificationOptions {
+            apns_push_type: Some(PushType::Background),
+            apns_priority: Some(Priority::High),
+            ..Default::default()
+        };
+        let payload = DefaultNotificationBuilder::new()
+            .set_content_available()
+            .build("token", options);
+
+        assert!(matches!(payload.validate(), Err(Error::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn test_validate_voip_push_with_alert() {
+        let options = NotificationOptions {
+            apns_push_type: Some(PushType::Voip),
+            ..Default::default()
+        };
+
+        let payload = DefaultNotificationBuilder::new().build("token", options.clone());
+        assert!(payload.validate().is_ok());
+
+        let payload = DefaultNotificationBuilder::new()
+            .set_title("Call")
+            .build("token", options);
+        assert!(matches!(payload.validate(), Err(Error::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn test_schema_json_contains_all_aps_keys() {
+        let schema = APS::schema_json();
+        let schema = schema.as_object().unwrap();
+
+        let aps = APS {
+            alert: Some(APSAlert::Body("body".into())),
+            badge: Some(1),
+            sound: Some(APSSound::Sound("ping".into())),
+            thread_id: Some("thread".into()),
+            content_available: Some(1),
+            category: Some("cat1".into()),
+            mutable_content: Some(1),
+            url_args: Some(vec!["arg1".into()]),
+            target_content_id: Some("scene".into()),
+            interruption_level: Some(InterruptionLevel::Passive),
+            relevance_score: Some(0.5),
+            event: Some(LiveActivityEvent::Update),
+            timestamp: Some(1),
+            content_state: Some(json!({})),
+            stale_date: Some(1),
+            dismissal_date: Some(1),
+            attributes_type: Some("Attributes".into()),
+            attributes: Some(json!({})),
+            filter_criteria: Some("team-a".into()),
+        };
+
+        let full = serde_json::to_value(aps).unwrap();
+        let full = full.as_object().unwrap();
+
+        assert_eq!(full.len(), schema.len());
+
+        for key in full.keys() {
+            assert!(schema.contains_key(key), "missing {} in schema", key);
+        }
+
+        assert!(schema.contains_key("interruption-level"));
+        assert!(schema.contains_key("relevance-score"));
+    }
 }
@@ -1,6 +1,8 @@
 //! The APNs response types
 
+use chrono::{DateTime, Utc};
 use std::fmt;
+use std::time::Duration;
 
 /// The response data from APNs.
 #[derive(Debug)]
@@ -12,6 +14,23 @@ pub struct Response {
     /// generated by APNs.
     pub apns_id: Option<String>,
 
+    /// The `apns-id` sent with the request, if any. APNs echoes it back in
+    /// `apns_id`, see [`Response::apns_id_matches_request`].
+    pub requested_apns_id: Option<String>,
+
+    /// A unique id generated by APNs for broadcast (channel) pushes. `None`
+    /// for regular device pushes.
+    pub apns_unique_id: Option<String>,
+
+    /// How long APNs asks to wait before retrying, from the `Retry-After`
+    /// header. Usually only present with HTTP 429 and 503 responses.
+    pub retry_after: Option<Duration>,
+
+    /// How long APNs took to respond, from sending the request until
+    /// receiving the response headers. Set by the client for both successful
+    /// and rejected notifications.
+    pub latency: Option<Duration>,
+
     /// The HTTP response code.
     ///
     /// * 200 Success
@@ -23,6 +42,10 @@ pub struct Response {
     /// * 429 The server received too many requests for the same device token.
     /// * 500 Internal server error.
     /// * 503 The server is shutting down and unavailable.
+    ///
+    /// APNs itself only answers successful notifications with 200. Any other
+    /// 2xx code, e.g. from a proxy in between, is also treated as success
+    /// and kept here as is.
     pub code: u16,
 }
 
@@ -41,8 +64,109 @@ pub struct ErrorBody {
     pub timestamp: Option<u64>,
 }
 
+impl Response {
+    /// A response with the given HTTP status and no other data, e.g. to test
+    /// code handling the results of [`Client::send`](crate::Client::send).
+    ///
+    /// ```rust
+    /// # use a2::{ErrorReason, Response};
+    /// # use std::time::Duration;
+    /// let accepted = Response::new(200).with_apns_id("a-test-apns-id");
+    /// let throttled = Response::rejected(429, ErrorReason::TooManyRequests).with_retry_after(Duration::from_secs(60));
+    ///
+    /// assert_eq!(Some("a-test-apns-id"), accepted.apns_id.as_deref());
+    /// assert_eq!(Some(Duration::from_secs(60)), throttled.retry_after);
+    /// ```
+    pub fn new(code: u16) -> Self {
+        Response {
+            error: None,
+            apns_id: None,
+            requested_apns_id: None,
+            apns_unique_id: None,
+            retry_after: None,
+            latency: None,
+            code,
+        }
+    }
+
+    /// A response of APNs rejecting the notification with the given HTTP
+    /// status and reason.
+    pub fn rejected(code: u16, reason: ErrorReason) -> Self {
+        Response {
+            error: Some(ErrorBody::new(reason)),
+            ..Response::new(code)
+        }
+    }
+
+    /// Sets the `apns_id`, as if echoed back by APNs.
+    pub fn with_apns_id<S: Into<String>>(mut self, apns_id: S) -> Self {
+        self.apns_id = Some(apns_id.into());
+        self
+    }
+
+    /// Sets the `retry_after`, as if given by a `Retry-After` header.
+    pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = Some(retry_after);
+        self
+    }
+
+    /// False if APNs responded with a different `apns-id` than the one
+    /// sent, which hints at a proxy rewriting the headers. True if no id was
+    /// sent.
+    pub fn apns_id_matches_request(&self) -> bool {
+        match (&self.requested_apns_id, &self.apns_id) {
+            (Some(requested), Some(responded)) => requested.eq_ignore_ascii_case(responded),
+            _ => true,
+        }
+    }
+
+    /// True if the device token should be removed: APNs rejected it as
+    /// `Unregistered`, `BadDeviceToken` or `DeviceTokenNotForTopic`.
+    ///
+    /// A `BadDeviceToken` can also mean the token is for the other APNs
+    /// environment, see [`ErrorReason::is_environment_mismatch`].
+    pub fn should_drop_token(&self) -> bool {
+        matches!(
+            self.error,
+            Some(ErrorBody {
+                reason: ErrorReason::Unregistered | ErrorReason::BadDeviceToken | ErrorReason::DeviceTokenNotForTopic,
+                ..
+            })
+        )
+    }
+}
+
+impl ErrorBody {
+    /// An error body with the given reason and no timestamp.
+    pub fn new(reason: ErrorReason) -> Self {
+        ErrorBody {
+            reason,
+            timestamp: None,
+        }
+    }
+
+    /// Sets the `timestamp` in milliseconds since the UNIX epoch, as sent
+    /// with [`ErrorReason::Unregistered`].
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// The `timestamp` as a date. For [`ErrorReason::Unregistered`] this is
+    /// when the device token became invalid; a token registered after it
+    /// supersedes the invalidation.
+    pub fn timestamp_utc(&self) -> Option<DateTime<Utc>> {
+        self.timestamp
+            .and_then(|ms| i64::try_from(ms).ok())
+            .and_then(DateTime::from_timestamp_millis)
+    }
+}
+
 /// A description what went wrong with the push notification.
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+///
+/// Reasons not known to this crate are kept in [`ErrorReason::Unknown`].
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String")]
 pub enum ErrorReason {
     /// The collapse identifier exceeds the maximum allowed size.
     BadCollapseId,
@@ -72,6 +196,9 @@ pub enum ErrorReason {
     /// Idle time out.
     IdleTimeout,
 
+    /// The `apns_push_type` in `NotificationOptions` is invalid.
+    InvalidPushType,
+
     /// The device token is not specified in the payload.
     MissingDeviceToken,
 
@@ -102,6 +229,13 @@ pub enum ErrorReason {
     /// The provider token is not valid or the token signature could not be verified.
     InvalidProviderToken,
 
+    /// The key id in the provider token is unrelated to the bundle id of
+    /// the topic.
+    UnrelatedKeyIdInToken,
+
+    /// The key id in the provider token is for the wrong environment.
+    BadEnvironmentKeyInToken,
+
     /// No provider certificate was used to connect to APNs and Authorization
     /// header was missing or no provider token was specified.
     MissingProviderToken,
@@ -112,6 +246,9 @@ pub enum ErrorReason {
     /// The request method was not `POST`.
     MethodNotAllowed,
 
+    /// The device token has expired.
+    ExpiredToken,
+
     /// The device token is inactive for the specified topic. You should stop sending
     /// notifications to this token.
     Unregistered,
@@ -133,11 +270,69 @@ pub enum ErrorReason {
 
     /// The server is shutting down.
     Shutdown,
+
+    /// A reason not known to this crate, holding the raw value from APNs.
+    Unknown(String),
+}
+
+impl From<String> for ErrorReason {
+    fn from(reason: String) -> Self {
+        match reason.as_str() {
+            "BadCollapseId" => ErrorReason::BadCollapseId,
+            "BadDeviceToken" => ErrorReason::BadDeviceToken,
+            "BadExpirationDate" => ErrorReason::BadExpirationDate,
+            "BadMessageId" => ErrorReason::BadMessageId,
+            "BadPriority" => ErrorReason::BadPriority,
+            "BadTopic" => ErrorReason::BadTopic,
+            "DeviceTokenNotForTopic" => ErrorReason::DeviceTokenNotForTopic,
+            "DuplicateHeaders" => ErrorReason::DuplicateHeaders,
+            "IdleTimeout" => ErrorReason::IdleTimeout,
+            "InvalidPushType" => ErrorReason::InvalidPushType,
+            "MissingDeviceToken" => ErrorReason::MissingDeviceToken,
+            "MissingTopic" => ErrorReason::MissingTopic,
+            "PayloadEmpty" => ErrorReason::PayloadEmpty,
+            "TopicDisallowed" => ErrorReason::TopicDisallowed,
+            "BadCertificate" => ErrorReason::BadCertificate,
+            "BadCertificateEnvironment" => ErrorReason::BadCertificateEnvironment,
+            "ExpiredProviderToken" => ErrorReason::ExpiredProviderToken,
+            "Forbidden" => ErrorReason::Forbidden,
+            "InvalidProviderToken" => ErrorReason::InvalidProviderToken,
+            "MissingProviderToken" => ErrorReason::MissingProviderToken,
+            "UnrelatedKeyIdInToken" => ErrorReason::UnrelatedKeyIdInToken,
+            "BadEnvironmentKeyInToken" => ErrorReason::BadEnvironmentKeyInToken,
+            "BadPath" => ErrorReason::BadPath,
+            "MethodNotAllowed" => ErrorReason::MethodNotAllowed,
+            "ExpiredToken" => ErrorReason::ExpiredToken,
+            "Unregistered" => ErrorReason::Unregistered,
+            "PayloadTooLarge" => ErrorReason::PayloadTooLarge,
+            "TooManyProviderTokenUpdates" => ErrorReason::TooManyProviderTokenUpdates,
+            "TooManyRequests" => ErrorReason::TooManyRequests,
+            "InternalServerError" => ErrorReason::InternalServerError,
+            "ServiceUnavailable" => ErrorReason::ServiceUnavailable,
+            "Shutdown" => ErrorReason::Shutdown,
+            _ => ErrorReason::Unknown(reason),
+        }
+    }
+}
+
+impl ErrorReason {
+    /// True if the error is commonly caused by sending to the wrong APNs
+    /// environment, e.g. using sandbox credentials or device tokens against
+    /// [`Endpoint::Production`](crate::Endpoint::Production).
+    pub fn is_environment_mismatch(&self) -> bool {
+        matches!(
+            self,
+            ErrorReason::BadCertificateEnvironment
+                | ErrorReason::BadEnvironmentKeyInToken
+                | ErrorReason::BadDeviceToken
+        )
+    }
 }
 
 impl fmt::Display for ErrorReason {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match *self {
+            ErrorReason::Unknown(ref reason) => return write!(f, "Unknown reason: {}", reason),
             ErrorReason::BadCollapseId =>
                 "The collapse identifier exceeds the maximum allowed size.",
             ErrorReason::BadDeviceToken =>
@@ -156,6 +351,8 @@ impl fmt::Display for ErrorReason {
                 "One or more headers were repeated.",
             ErrorReason::IdleTimeout =>
                 "Idle time out.",
+            ErrorReason::InvalidPushType =>
+                "The `apns_push_type` in `NotificationOptions` is invalid.",
             ErrorReason::MissingDeviceToken =>
                 "The device token is not specified in the payload.",
             ErrorReason::MissingTopic =>
@@ -174,12 +371,18 @@ impl fmt::Display for ErrorReason {
                 "The specified action is not allowed.",
             ErrorReason::InvalidProviderToken =>
                 "The provider token is not valid or the token signature could not be verified.",
+            ErrorReason::UnrelatedKeyIdInToken =>
+                "The key id in the provider token is unrelated to the bundle id of the topic.",
+            ErrorReason::BadEnvironmentKeyInToken =>
+                "The key id in the provider token is for the wrong environment.",
             ErrorReason::MissingProviderToken =>
                 "No provider certificate was used to connect to APNs and Authorization header was missing or no provider token was specified.",
             ErrorReason::BadPath =>
                 "The request path value is bad.",
             ErrorReason::MethodNotAllowed =>
                 "The request method was not `POST`.",
+            ErrorReason::ExpiredToken =>
+                "The device token has expired.",
             ErrorReason::Unregistered =>
                 "The device token is inactive for the specified topic. You should stop sending notifications to this token.",
             ErrorReason::PayloadTooLarge =>
@@ -205,6 +408,36 @@ mod tests {
     use super::*;
     use serde_json;
 
+    #[test]
+    fn test_constructing_responses() {
+        let response = Response::new(200).with_apns_id("a-test-apns-id");
+
+        assert_eq!(200, response.code);
+        assert_eq!(Some("a-test-apns-id"), response.apns_id.as_deref());
+        assert!(response.error.is_none());
+        assert!(response.apns_id_matches_request());
+
+        let response = Response {
+            error: Some(ErrorBody::new(ErrorReason::Unregistered).with_timestamp(1_700_000_000_000)),
+            ..Response::new(410)
+        };
+
+        assert!(response.should_drop_token());
+        assert_eq!(
+            Some(1_700_000_000_000),
+            response.error.as_ref().and_then(|error| error.timestamp)
+        );
+
+        let response =
+            Response::rejected(503, ErrorReason::ServiceUnavailable).with_retry_after(Duration::from_secs(5));
+
+        assert_eq!(Some(Duration::from_secs(5)), response.retry_after);
+        assert_eq!(
+            Some(&ErrorBody::new(ErrorReason::ServiceUnavailable)),
+            response.error.as_ref()
+        );
+    }
+
     #[test]
     fn test_error_response_parsing() {
         let errors = vec![
@@ -217,6 +450,7 @@ mod tests {
             (ErrorReason::DeviceTokenNotForTopic, "DeviceTokenNotForTopic", None),
             (ErrorReason::DuplicateHeaders, "DuplicateHeaders", None),
             (ErrorReason::IdleTimeout, "IdleTimeout", None),
+            (ErrorReason::InvalidPushType, "InvalidPushType", None),
             (ErrorReason::MissingDeviceToken, "MissingDeviceToken", None),
             (ErrorReason::MissingTopic, "MissingTopic", None),
             (ErrorReason::PayloadEmpty, "PayloadEmpty", None),
@@ -231,8 +465,11 @@ mod tests {
             (ErrorReason::Forbidden, "Forbidden", None),
             (ErrorReason::InvalidProviderToken, "InvalidProviderToken", None),
             (ErrorReason::MissingProviderToken, "MissingProviderToken", None),
+            (ErrorReason::UnrelatedKeyIdInToken, "UnrelatedKeyIdInToken", None),
+            (ErrorReason::BadEnvironmentKeyInToken, "BadEnvironmentKeyInToken", None),
             (ErrorReason::BadPath, "BadPath", None),
             (ErrorReason::MethodNotAllowed, "MethodNotAllowed", None),
+            (ErrorReason::ExpiredToken, "ExpiredToken", None),
             (ErrorReason::Unregistered, "Unregistered", Some(1508249865488u64)),
             (ErrorReason::PayloadTooLarge, "PayloadTooLarge", None),
             (
@@ -244,6 +481,7 @@ mod tests {
             (ErrorReason::InternalServerError, "InternalServerError", None),
             (ErrorReason::ServiceUnavailable, "ServiceUnavailable", None),
             (ErrorReason::Shutdown, "Shutdown", None),
+            (ErrorReason::Unknown("SomethingNew".to_string()), "SomethingNew", None),
         ];
 
         for error in errors.into_iter() {
@@ -270,4 +508,31 @@ mod tests {
             assert_eq!(expected_body, response_body);
         }
     }
+
+    #[test]
+    fn test_unregistered_timestamp_utc() {
+        let body: ErrorBody = serde_json::from_str(r#"{"reason":"Unregistered","timestamp":1508249865488}"#).unwrap();
+
+        let expected = DateTime::parse_from_rfc3339("2017-10-17T14:17:45.488Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(ErrorReason::Unregistered, body.reason);
+        assert_eq!(Some(expected), body.timestamp_utc());
+    }
+
+    #[test]
+    fn test_missing_timestamp_utc() {
+        let body: ErrorBody = serde_json::from_str(r#"{"reason":"BadDeviceToken"}"#).unwrap();
+
+        assert_eq!(None, body.timestamp_utc());
+    }
+
+    #[test]
+    fn test_unknown_error_reason_display() {
+        let reason = ErrorReason::from("SomethingNew".to_string());
+
+        assert_eq!(ErrorReason::Unknown("SomethingNew".to_string()), reason);
+        assert_eq!("Unknown reason: SomethingNew", reason.to_string());
+    }
 }
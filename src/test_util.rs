@@ -0,0 +1,586 @@
+//! An in-process fake APNs server for tests
+//!
+//! The [`FakeApns`] server speaks HTTP/2 over TLS with a self-signed
+//! certificate, validates requests the way APNs does and answers with
+//! configurable responses. Clients connect to it through
+//! [`FakeApns::connector`], which trusts the certificate and routes the APNs
+//! host names to the local server.
+//!
+//! For tests without any network, [`mock_client`] answers the requests of a
+//! client with a function instead.
+//!
+//! Only available with the `test-util` feature.
+//!
+//! ```rust
+//! # use a2::test_util::{FakeApns, FakeResponse};
+//! # use a2::{DefaultNotificationBuilder, NotificationBuilder};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//! let server = FakeApns::start().await?;
+//! server.respond_with("stale-token", FakeResponse::unregistered(1508249865488));
+//!
+//! let client = server.client();
+//!
+//! let payload = DefaultNotificationBuilder::new().set_body("Hi").build("a-token", Default::default());
+//! assert_eq!(200, client.send(payload).await?.code);
+//!
+//! let payload = DefaultNotificationBuilder::new().set_body("Hi").build("stale-token", Default::default());
+//! assert!(client.send(payload).await.is_err());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::{BoxError, Client, ClientBuilder, ClientConfig, Transport};
+use crate::error::Error;
+use http::header::RETRY_AFTER;
+use http::{HeaderMap, Method, StatusCode, Uri};
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http2;
+use hyper::service::service_fn;
+use hyper_rustls::HttpsConnector;
+use hyper_util::client::legacy::connect::Connection;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio_rustls::TlsAcceptor;
+use uuid::Uuid;
+
+const CERTIFICATE: &[u8] = include_bytes!("../test_cert/fake_apns.crt");
+const PRIVATE_KEY: &[u8] = include_bytes!("../test_cert/fake_apns.key");
+
+const MAX_PAYLOAD_SIZE: usize = 4096;
+
+/// The response the [`FakeApns`] server gives to a device token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeResponse {
+    status: StatusCode,
+    reason: Option<String>,
+    timestamp: Option<u64>,
+    retry_after: Option<u64>,
+}
+
+impl FakeResponse {
+    /// The notification was accepted (HTTP 200).
+    pub fn ok() -> Self {
+        Self {
+            status: StatusCode::OK,
+            reason: None,
+            timestamp: None,
+            retry_after: None,
+        }
+    }
+
+    /// The notification was rejected with the given HTTP status and reason,
+    /// e.g. `400` and `"BadDeviceToken"`.
+    pub fn error(status: u16, reason: &str) -> Self {
+        Self {
+            status: StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_REQUEST),
+            reason: Some(reason.to_string()),
+            timestamp: None,
+            retry_after: None,
+        }
+    }
+
+    /// The device token is no longer active (HTTP 410), since the given
+    /// UNIX timestamp in milliseconds.
+    pub fn unregistered(timestamp_ms: u64) -> Self {
+        Self::error(410, "Unregistered").with_timestamp(timestamp_ms)
+    }
+
+    /// Too many requests for the device token (HTTP 429), asking to retry
+    /// after the given number of seconds.
+    pub fn too_many_requests(retry_after_secs: u64) -> Self {
+        Self::error(429, "TooManyRequests").with_retry_after(retry_after_secs)
+    }
+
+    /// Adds a `timestamp` in milliseconds to the error body.
+    pub fn with_timestamp(mut self, timestamp_ms: u64) -> Self {
+        self.timestamp = Some(timestamp_ms);
+        self
+    }
+
+    /// Adds a `Retry-After` header in seconds to the response.
+    pub fn with_retry_after(mut self, retry_after_secs: u64) -> Self {
+        self.retry_after = Some(retry_after_secs);
+        self
+    }
+}
+
+impl Default for FakeResponse {
+    fn default() -> Self {
+        Self::ok()
+    }
+}
+
+/// A request received by the [`FakeApns`] server.
+#[derive(Debug, Clone)]
+pub struct FakeRequest {
+    /// The device token from the request path
+    pub device_token: String,
+    /// The request headers
+    pub headers: HeaderMap,
+    /// The raw payload
+    pub body: Bytes,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    responses: HashMap<String, FakeResponse>,
+    requests: Vec<FakeRequest>,
+}
+
+/// An in-process HTTP/2 server behaving like APNs. Stops when dropped.
+#[derive(Debug)]
+pub struct FakeApns {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    task: JoinHandle<()>,
+}
+
+impl FakeApns {
+    /// Starts the server on a random local port. Must be called within a
+    /// Tokio runtime.
+    pub async fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let addr = listener.local_addr()?;
+        let acceptor = TlsAcceptor::from(Arc::new(server_config()?));
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let task = tokio::spawn(serve(listener, acceptor, state.clone()));
+
+        Ok(Self { addr, state, task })
+    }
+
+    /// The local address the server listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Answers every request to the device token with the given response.
+    /// Tokens without a configured response get [`FakeResponse::ok`].
+    pub fn respond_with(&self, device_token: &str, response: FakeResponse) {
+        self.state.lock().responses.insert(device_token.to_string(), response);
+    }
+
+    /// All valid requests received so far.
+    pub fn requests(&self) -> Vec<FakeRequest> {
+        self.state.lock().requests.clone()
+    }
+
+    /// A connector sending all requests, whatever the endpoint, to this
+    /// server and trusting its certificate. Use with
+    /// [`ClientBuilder::connector`](crate::client::ClientBuilder::connector)
+    /// to combine it with a signer or a custom config.
+    pub fn connector(&self) -> HttpsConnector<FakeApnsConnector> {
+        let mut roots = rustls::RootCertStore::empty();
+
+        for cert in rustls_pemfile::certs(&mut &CERTIFICATE[..]).flatten() {
+            // The certificate is bundled with the crate and known to be valid.
+            let _ = roots.add(cert);
+        }
+
+        let mut config = rustls::ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        config.alpn_protocols = vec![b"h2".to_vec()];
+
+        HttpsConnector::from((FakeApnsConnector { addr: self.addr }, config))
+    }
+
+    /// A client without authentication connected to this server.
+    pub fn client(&self) -> Client {
+        Client::builder()
+            .connector(self.connector())
+            .config(ClientConfig::default())
+            .build()
+    }
+}
+
+impl Drop for FakeApns {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Connects to a [`FakeApns`] server, whatever the requested host.
+#[derive(Debug, Clone)]
+pub struct FakeApnsConnector {
+    addr: SocketAddr,
+}
+
+impl tower_service::Service<Uri> for FakeApnsConnector {
+    type Response = TokioIo<TcpStream>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<TokioIo<TcpStream>>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: Uri) -> Self::Future {
+        let addr = self.addr;
+        Box::pin(async move { Ok(TokioIo::new(TcpStream::connect(addr).await?)) })
+    }
+}
+
+/// Builds a client that answers every request with `handler` instead of
+/// sending it to APNs. The handler gets the request with its complete body,
+/// so tests can assert on the headers and payload, and returns the canned
+/// response, parsed like a real APNs response.
+///
+/// ```rust
+/// # use a2::test_util::mock_client;
+/// # use a2::{Client, DefaultNotificationBuilder, Error, ErrorReason, NotificationBuilder};
+/// # #[tokio::main]
+/// # async fn main() {
+/// let client = mock_client(Client::builder(), |_request| {
+///     http::Response::builder()
+///         .status(410)
+///         .body(r#"{"reason":"Unregistered","timestamp":1508249865488}"#.into())
+///         .unwrap()
+/// });
+///
+/// let payload = DefaultNotificationBuilder::new().set_body("Hi").build("a-token", Default::default());
+///
+/// match client.send(payload).await {
+///     Err(Error::ResponseError(response)) => {
+///         assert_eq!(410, response.code);
+///         assert_eq!(ErrorReason::Unregistered, response.error.unwrap().reason);
+///     }
+///     result => panic!("unexpected result {:?}", result),
+/// }
+/// # }
+/// ```
+pub fn mock_client<C, F>(builder: ClientBuilder<C>, handler: F) -> Client
+where
+    C: tower_service::Service<Uri> + Clone + Send + Sync + 'static,
+    C::Response: hyper::rt::Read + hyper::rt::Write + Connection + Unpin + Send + 'static,
+    C::Future: Send + 'static,
+    C::Error: Into<BoxError>,
+    F: Fn(&http::Request<Bytes>) -> http::Response<Bytes> + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+
+    let transport = Transport::from_fn(move |request| {
+        let handler = handler.clone();
+
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let body = body.collect().await?.to_bytes();
+            let response = handler(&http::Request::from_parts(parts, body));
+
+            Ok(response.map(|body| Full::new(body).map_err(Error::from).boxed()))
+        })
+    });
+
+    builder.build_with_transport(transport)
+}
+
+fn server_config() -> io::Result<rustls::ServerConfig> {
+    let invalid_data = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+
+    let certs = rustls_pemfile::certs(&mut &CERTIFICATE[..]).collect::<Result<Vec<_>, _>>()?;
+    let key = rustls_pemfile::private_key(&mut &PRIVATE_KEY[..])?
+        .ok_or_else(|| invalid_data("missing private key".to_string()))?;
+
+    let mut config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| invalid_data(e.to_string()))?;
+    config.alpn_protocols = vec![b"h2".to_vec()];
+
+    Ok(config)
+}
+
+async fn serve(listener: TcpListener, acceptor: TlsAcceptor, state: Arc<Mutex<State>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let acceptor = acceptor.clone();
+        let state = state.clone();
+
+        tokio::spawn(async move {
+            let Ok(stream) = acceptor.accept(stream).await else {
+                return;
+            };
+
+            let service = service_fn(move |request| handle(state.clone(), request));
+
+            let _ = http2::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(stream), service)
+                .await;
+        });
+    }
+}
+
+async fn handle(
+    state: Arc<Mutex<State>>,
+    request: hyper::Request<Incoming>,
+) -> Result<hyper::Response<Full<Bytes>>, Infallible> {
+    let (parts, body) = request.into_parts();
+    let body = body.collect().await.map(|b| b.to_bytes()).unwrap_or_default();
+
+    let apns_id = parts
+        .headers
+        .get("apns-id")
+        .and_then(|v| v.to_str().ok())
+        .map(String::from)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let response = match validate(&parts.method, &parts.uri, &parts.headers, &body) {
+        Err(response) => response,
+        Ok(device_token) => {
+            let mut state = state.lock();
+            let response = state.responses.get(&device_token).cloned().unwrap_or_default();
+
+            state.requests.push(FakeRequest {
+                device_token,
+                headers: parts.headers,
+                body,
+            });
+
+            response
+        }
+    };
+
+    Ok(respond(response, &apns_id))
+}
+
+/// Checks the request like APNs does, returning the device token.
+fn validate(method: &Method, uri: &Uri, headers: &HeaderMap, body: &Bytes) -> Result<String, FakeResponse> {
+    if method != Method::POST {
+        return Err(FakeResponse::error(405, "MethodNotAllowed"));
+    }
+
+    let Some(device_token) = uri.path().strip_prefix("/3/device/") else {
+        return Err(FakeResponse::error(404, "BadPath"));
+    };
+
+    if device_token.is_empty() {
+        return Err(FakeResponse::error(400, "MissingDeviceToken"));
+    }
+
+    let header = |name: &str| headers.get(name).map(|v| v.to_str().unwrap_or_default());
+
+    if let Some(apns_id) = header("apns-id") {
+        if Uuid::parse_str(apns_id).is_err() {
+            return Err(FakeResponse::error(400, "BadMessageId"));
+        }
+    }
+
+    if let Some(priority) = header("apns-priority") {
+        if !matches!(priority, "1" | "5" | "10") {
+            return Err(FakeResponse::error(400, "BadPriority"));
+        }
+    }
+
+    if let Some(push_type) = header("apns-push-type") {
+        let known = [
+            "alert",
+            "background",
+            "location",
+            "voip",
+            "complication",
+            "fileprovider",
+            "mdm",
+            "liveactivity",
+            "pushtotalk",
+        ];
+
+        if !known.contains(&push_type) {
+            return Err(FakeResponse::error(400, "InvalidPushType"));
+        }
+    }
+
+    if let Some(expiration) = header("apns-expiration") {
+        if expiration.parse::<u64>().is_err() {
+            return Err(FakeResponse::error(400, "BadExpirationDate"));
+        }
+    }
+
+    if let Some(collapse_id) = header("apns-collapse-id") {
+        if collapse_id.len() > 64 {
+            return Err(FakeResponse::error(400, "BadCollapseId"));
+        }
+    }
+
+    if body.is_empty() {
+        return Err(FakeResponse::error(400, "PayloadEmpty"));
+    }
+
+    if body.len() > MAX_PAYLOAD_SIZE {
+        return Err(FakeResponse::error(413, "PayloadTooLarge"));
+    }
+
+    Ok(device_token.to_string())
+}
+
+fn respond(response: FakeResponse, apns_id: &str) -> hyper::Response<Full<Bytes>> {
+    let mut builder = hyper::Response::builder()
+        .status(response.status)
+        .header("apns-id", apns_id);
+
+    if let Some(retry_after) = response.retry_after {
+        builder = builder.header(RETRY_AFTER, retry_after);
+    }
+
+    let body = match response.reason {
+        Some(reason) => {
+            let mut body = json!({ "reason": reason });
+
+            if let Some(timestamp) = response.timestamp {
+                body["timestamp"] = json!(timestamp);
+            }
+
+            Bytes::from(body.to_string())
+        }
+        None => Bytes::new(),
+    };
+
+    builder
+        .body(Full::new(body))
+        .unwrap_or_else(|_| hyper::Response::new(Full::new(Bytes::new())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::notification::{DefaultNotificationBuilder, NotificationBuilder, NotificationOptions};
+    use crate::response::{ErrorReason, Response};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_mock_client_unregistered() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let requests = seen.clone();
+
+        let client = mock_client(Client::builder(), move |request| {
+            requests.lock().push(request.body().clone());
+
+            http::Response::builder()
+                .status(410)
+                .header("apns-id", "CDB7C5E5-7B1A-4F5C-9E11-1D3F1D0F1E01")
+                .body(Bytes::from(r#"{"reason":"Unregistered","timestamp":1508249865488}"#))
+                .unwrap()
+        });
+
+        let payload = DefaultNotificationBuilder::new()
+            .set_body("Hello")
+            .build("a_test_id", Default::default());
+
+        let Err(Error::ResponseError(response)) = client.send(payload).await else {
+            panic!("expected a response error");
+        };
+
+        assert_eq!(410, response.code);
+        assert_eq!(
+            Some("CDB7C5E5-7B1A-4F5C-9E11-1D3F1D0F1E01"),
+            response.apns_id.as_deref()
+        );
+
+        let error = response.error.unwrap();
+        assert_eq!(ErrorReason::Unregistered, error.reason);
+        assert_eq!(Some(1508249865488), error.timestamp);
+
+        assert_eq!(vec![Bytes::from(r#"{"aps":{"alert":"Hello"}}"#)], *seen.lock());
+    }
+
+    #[tokio::test]
+    async fn test_client_end_to_end_ok() {
+        let server = FakeApns::start().await.unwrap();
+        let client = server.client();
+
+        let payload = DefaultNotificationBuilder::new().set_body("Hello").build(
+            "a_test_id",
+            NotificationOptions {
+                apns_topic: Some("com.example.app"),
+                ..Default::default()
+            },
+        );
+
+        let response = client.send(payload).await.unwrap();
+
+        assert_eq!(200, response.code);
+        assert!(response.apns_id.is_some());
+
+        let requests = server.requests();
+
+        assert_eq!(1, requests.len());
+        assert_eq!("a_test_id", requests[0].device_token);
+        assert_eq!("com.example.app", requests[0].headers.get("apns-topic").unwrap());
+        assert_eq!(
+            r#"{"aps":{"alert":"Hello"}}"#,
+            String::from_utf8_lossy(&requests[0].body)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_end_to_end_unregistered() {
+        let server = FakeApns::start().await.unwrap();
+        server.respond_with("a_test_id", FakeResponse::unregistered(1508249865488));
+
+        let client = server.client();
+        let payload = DefaultNotificationBuilder::new()
+            .set_body("Hello")
+            .build("a_test_id", Default::default());
+
+        let Err(Error::ResponseError(response)) = client.send(payload).await else {
+            panic!("expected a response error");
+        };
+        let Response { code, error, .. } = *response;
+
+        let error = error.unwrap();
+
+        assert_eq!(410, code);
+        assert_eq!(ErrorReason::Unregistered, error.reason);
+        assert_eq!(Some(1508249865488), error.timestamp);
+    }
+
+    #[tokio::test]
+    async fn test_client_end_to_end_retry_after() {
+        let server = FakeApns::start().await.unwrap();
+        server.respond_with("a_test_id", FakeResponse::too_many_requests(30));
+
+        let client = server.client();
+        let payload = DefaultNotificationBuilder::new()
+            .set_body("Hello")
+            .build("a_test_id", Default::default());
+
+        let Err(Error::ResponseError(response)) = client.send(payload).await else {
+            panic!("expected a response error");
+        };
+
+        assert_eq!(429, response.code);
+        assert_eq!(Some(Duration::from_secs(30)), response.retry_after);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_apns_id_is_rejected() {
+        let server = FakeApns::start().await.unwrap();
+        let client = server.client();
+
+        let payload = DefaultNotificationBuilder::new().set_body("Hello").build(
+            "a_test_id",
+            NotificationOptions {
+                apns_id: Some("not-a-uuid"),
+                ..Default::default()
+            },
+        );
+
+        let Err(Error::ResponseError(response)) = client.send(payload).await else {
+            panic!("expected a response error");
+        };
+
+        assert_eq!(400, response.code);
+        assert_eq!(ErrorReason::BadMessageId, response.error.unwrap().reason);
+        assert!(server.requests().is_empty());
+    }
+}